@@ -0,0 +1,138 @@
+//! Frame sequences, for animated output of graphs over time.
+
+use crate::{ppm, Dimension, Graph, Playhead, Plot};
+use rsound_output::Buffer;
+
+/// Receives frames of packed RGB pixels, one after another
+pub trait Frames {
+    fn push(&mut self, rgb: &[u8]);
+}
+
+/// Evenly spaced frame times over a piece
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct Animation {
+    duration: f64,
+    fps: f64,
+    tempo: f64,
+}
+
+impl Animation {
+    /// Over `duration` in block duration units, one unit a second unless a tempo is given
+    pub fn new(duration: f64, fps: f64) -> Self {
+        assert!(duration.is_finite(), "there has to be a finite duration");
+        assert!(fps > 0.0 && fps.is_finite(), "there has to be a frame rate");
+        Self {
+            duration: duration.max(0.0),
+            fps,
+            tempo: 1.0,
+        }
+    }
+
+    /// Block duration units per second, beats per minute divided by 60 for beats
+    pub fn with_tempo(mut self, units_per_second: f64) -> Self {
+        assert!(
+            units_per_second > 0.0 && units_per_second.is_finite(),
+            "there has to be a tempo"
+        );
+        self.tempo = units_per_second;
+        self
+    }
+
+    pub fn fps(&self) -> f64 {
+        self.fps
+    }
+
+    /// Time of each frame in duration units, starting at zero
+    pub fn times(&self) -> Vec<f64> {
+        let seconds = self.duration / self.tempo;
+        let count = ((seconds * self.fps).ceil() as usize).max(1);
+        (0..count)
+            .map(|frame| frame as f64 / self.fps * self.tempo)
+            .collect()
+    }
+
+    /// Graph built for each frame time, drawn on a canvas of `size`
+    pub fn render<G, F, S>(&self, size: &Dimension, frame: F, sink: &mut S)
+    where
+        G: Graph,
+        F: Fn(f64) -> G,
+        S: Frames,
+    {
+        for time in self.times() {
+            let mut renderer = ppm::Renderer::new(size);
+            frame(time).draw(&mut renderer);
+            sink.push(renderer.get_buffer());
+        }
+    }
+
+    /// Playhead moving over its graph, which is drawn only once
+    pub fn sweep<G: Plot, S: Frames>(&self, playhead: &mut Playhead<G>, sink: &mut S) {
+        let background = playhead.background(ppm::Renderer::new(playhead.size()));
+        for time in self.times() {
+            playhead.seek(time);
+            sink.push(playhead.frame(&background).get_buffer());
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{Block, Roll, Viewport};
+
+    #[derive(Default)]
+    struct Collect(Vec<Vec<u8>>);
+
+    impl Frames for Collect {
+        fn push(&mut self, rgb: &[u8]) {
+            self.0.push(rgb.to_vec());
+        }
+    }
+
+    #[test]
+    #[should_panic(expected = "tempo")]
+    fn zero_tempo() {
+        Animation::new(4.0, 25.0).with_tempo(0.0);
+    }
+
+    #[test]
+    #[should_panic(expected = "finite duration")]
+    fn endless_duration() {
+        Animation::new(f64::INFINITY, 25.0);
+    }
+
+    #[test]
+    fn frame_times_follow_tempo() {
+        let animation = Animation::new(8.0, 4.0).with_tempo(2.0);
+
+        assert_eq!(
+            animation.times(),
+            vec![0.0, 0.5, 1.0, 1.5, 2.0, 2.5, 3.0, 3.5, 4.0, 4.5, 5.0, 5.5, 6.0, 6.5, 7.0, 7.5]
+        );
+        assert_eq!(Animation::new(0.0, 30.0).times(), vec![0.0]);
+    }
+
+    #[test]
+    fn scrolls_and_sweeps() {
+        let blocks: Vec<Block> = (0..8).map(|x| Block(2.0, (x % 3 + 1) as f64)).collect();
+        let animation = Animation::new(16.0, 1.0);
+        let size = Dimension::new(240.0, 140.0);
+        let mut scrolled = Collect::default();
+        animation.render(
+            &size,
+            |time| {
+                Roll::new(&blocks)
+                    .with_viewport(Viewport::new(time, time + 4.0).with_values(1.0, 3.0))
+            },
+            &mut scrolled,
+        );
+        let mut swept = Collect::default();
+        animation.sweep(&mut Playhead::new(Roll::new(&blocks)), &mut swept);
+
+        assert_eq!(scrolled.0.len(), 16);
+        assert!(scrolled.0.iter().all(|x| x.len() == 240 * 140 * 3));
+        assert_ne!(scrolled.0[0], scrolled.0[1], "window moves");
+        assert_eq!(swept.0.len(), 16);
+        assert_ne!(swept.0[0], swept.0[15], "playhead moves");
+    }
+}
@@ -0,0 +1,472 @@
+use std::fmt;
+use std::str::FromStr;
+
+/// RGBA color, alpha 255 being fully opaque
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct Color(pub u8, pub u8, pub u8, pub u8);
+
+impl From<u32> for Color {
+    fn from(raw: u32) -> Self {
+        Self(
+            ((raw >> 16) & 255) as u8,
+            ((raw >> 8) & 255) as u8,
+            (raw & 255) as u8,
+            255,
+        )
+    }
+}
+
+impl Color {
+    pub fn new(r: u8, g: u8, b: u8) -> Self {
+        Self(r, g, b, 255)
+    }
+
+    pub fn with_alpha(mut self, alpha: u8) -> Self {
+        self.3 = alpha;
+        self
+    }
+
+    pub fn is_opaque(&self) -> bool {
+        self.3 == 255
+    }
+
+    /// CSS color function
+    pub fn rgb(&self) -> String {
+        if self.is_opaque() {
+            format!("rgb({}, {}, {})", self.0, self.1, self.2)
+        } else {
+            format!(
+                "rgba({}, {}, {}, {})",
+                self.0,
+                self.1,
+                self.2,
+                round(self.3 as f64 / 255.0)
+            )
+        }
+    }
+
+    /// Hue in degrees, saturation and lightness within 0..=1
+    pub fn from_hsl(h: f64, s: f64, l: f64) -> Self {
+        let (s, l) = (s.clamp(0.0, 1.0), l.clamp(0.0, 1.0));
+        let chroma = (1.0 - (2.0 * l - 1.0).abs()) * s;
+        from_chroma(h, chroma, l - chroma / 2.0)
+    }
+
+    pub fn to_hsl(&self) -> (f64, f64, f64) {
+        let (max, min, hue) = self.hue();
+        let l = (max + min) / 2.0;
+        let s = if max == min {
+            0.0
+        } else {
+            (max - min) / (1.0 - (2.0 * l - 1.0).abs())
+        };
+        (hue, s, l)
+    }
+
+    /// Hue in degrees, saturation and value within 0..=1
+    pub fn from_hsv(h: f64, s: f64, v: f64) -> Self {
+        let (s, v) = (s.clamp(0.0, 1.0), v.clamp(0.0, 1.0));
+        let chroma = v * s;
+        from_chroma(h, chroma, v - chroma)
+    }
+
+    pub fn to_hsv(&self) -> (f64, f64, f64) {
+        let (max, min, hue) = self.hue();
+        let s = if max == 0.0 { 0.0 } else { (max - min) / max };
+        (hue, s, max)
+    }
+
+    /// Raises HSL lightness by `amount`, keeping alpha
+    pub fn lighten(&self, amount: f64) -> Self {
+        let (h, s, l) = self.to_hsl();
+        Self::from_hsl(h, s, l + amount).with_alpha(self.3)
+    }
+
+    pub fn darken(&self, amount: f64) -> Self {
+        self.lighten(-amount)
+    }
+
+    /// Blends towards `other`, `t` of 0 being self and 1 being other
+    pub fn mix(&self, other: &Color, t: f64) -> Self {
+        let t = t.clamp(0.0, 1.0);
+        let lerp = |a: u8, b: u8| (a as f64 + (b as f64 - a as f64) * t).round() as u8;
+        Self(
+            lerp(self.0, other.0),
+            lerp(self.1, other.1),
+            lerp(self.2, other.2),
+            lerp(self.3, other.3),
+        )
+    }
+
+    /// Largest and smallest channel, with hue in degrees
+    fn hue(&self) -> (f64, f64, f64) {
+        let (r, g, b) = (
+            self.0 as f64 / 255.0,
+            self.1 as f64 / 255.0,
+            self.2 as f64 / 255.0,
+        );
+        let max = r.max(g).max(b);
+        let min = r.min(g).min(b);
+        let delta = max - min;
+        let hue = if delta == 0.0 {
+            0.0
+        } else if max == r {
+            60.0 * ((g - b) / delta)
+        } else if max == g {
+            60.0 * ((b - r) / delta + 2.0)
+        } else {
+            60.0 * ((r - g) / delta + 4.0)
+        };
+        (max, min, hue.rem_euclid(360.0))
+    }
+}
+
+fn from_chroma(hue: f64, chroma: f64, m: f64) -> Color {
+    let sector = hue.rem_euclid(360.0) / 60.0;
+    let x = chroma * (1.0 - (sector % 2.0 - 1.0).abs());
+    let (r, g, b) = match sector as usize {
+        0 => (chroma, x, 0.0),
+        1 => (x, chroma, 0.0),
+        2 => (0.0, chroma, x),
+        3 => (0.0, x, chroma),
+        4 => (x, 0.0, chroma),
+        _ => (chroma, 0.0, x),
+    };
+    let channel = |c: f64| ((c + m).clamp(0.0, 1.0) * 255.0).round() as u8;
+    Color::new(channel(r), channel(g), channel(b))
+}
+
+fn round(value: f64) -> f64 {
+    (value * 1000.0).round() / 1000.0
+}
+
+impl fmt::Display for Color {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "#{:02x}{:02x}{:02x}", self.0, self.1, self.2)?;
+        if !self.is_opaque() {
+            write!(f, "{:02x}", self.3)?;
+        }
+        Ok(())
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct ParseColorError(String);
+
+impl fmt::Display for ParseColorError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "invalid color: {:?}", self.0)
+    }
+}
+
+impl std::error::Error for ParseColorError {}
+
+/// Accepts `#rgb`, `#rgba`, `#rrggbb`, `#rrggbbaa`, `rgb()`, `rgba()`,
+/// `hsl()`, `hsla()` and CSS color names
+impl FromStr for Color {
+    type Err = ParseColorError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let src = s.trim().to_ascii_lowercase();
+        let color = if let Some(hex) = src.strip_prefix('#') {
+            parse_hex(hex)
+        } else if let Some((func, args)) = src.strip_suffix(')').and_then(|x| x.split_once('(')) {
+            parse_function(func.trim(), args)
+        } else {
+            named(&src)
+        };
+        color.ok_or_else(|| ParseColorError(s.to_string()))
+    }
+}
+
+fn parse_hex(hex: &str) -> Option<Color> {
+    if !hex.chars().all(|c| c.is_ascii_hexdigit()) {
+        return None;
+    }
+    let digits: Vec<u8> = match hex.len() {
+        3 | 4 => hex
+            .chars()
+            .map(|c| c.to_digit(16).map(|d| d as u8 * 17))
+            .collect::<Option<_>>()?,
+        6 | 8 => (0..hex.len())
+            .step_by(2)
+            .map(|i| u8::from_str_radix(&hex[i..i + 2], 16).ok())
+            .collect::<Option<_>>()?,
+        _ => return None,
+    };
+    let alpha = digits.get(3).copied().unwrap_or(255);
+    Some(Color(digits[0], digits[1], digits[2], alpha))
+}
+
+fn parse_function(func: &str, args: &str) -> Option<Color> {
+    let args = args.replace([',', '/'], " ");
+    let args: Vec<&str> = args.split_whitespace().collect();
+    if args.len() != 3 && args.len() != 4 {
+        return None;
+    }
+    let alpha = match args.get(3) {
+        Some(alpha) => (parse_fraction(alpha)? * 255.0).round() as u8,
+        None => 255,
+    };
+    let color = match func {
+        "rgb" | "rgba" => {
+            let channel = |arg: &str| match arg.strip_suffix('%') {
+                Some(pct) => pct.parse::<f64>().ok().map(|x| x * 2.55),
+                None => arg.parse::<f64>().ok(),
+            };
+            let channel = |arg: &str| channel(arg).map(|x| x.clamp(0.0, 255.0).round() as u8);
+            Color::new(channel(args[0])?, channel(args[1])?, channel(args[2])?)
+        }
+        "hsl" | "hsla" => {
+            let hue = args[0].strip_suffix("deg").unwrap_or(args[0]);
+            let percent = |arg: &str| {
+                arg.strip_suffix('%')
+                    .unwrap_or(arg)
+                    .parse::<f64>()
+                    .ok()
+                    .map(|x| x / 100.0)
+            };
+            Color::from_hsl(hue.parse().ok()?, percent(args[1])?, percent(args[2])?)
+        }
+        _ => return None,
+    };
+    Some(color.with_alpha(alpha))
+}
+
+/// Alpha either as fraction or percentage
+fn parse_fraction(arg: &str) -> Option<f64> {
+    let value = match arg.strip_suffix('%') {
+        Some(pct) => pct.parse::<f64>().ok()? / 100.0,
+        None => arg.parse::<f64>().ok()?,
+    };
+    Some(value.clamp(0.0, 1.0))
+}
+
+fn named(name: &str) -> Option<Color> {
+    if name == "transparent" {
+        return Some(Color(0, 0, 0, 0));
+    }
+    NAMED
+        .binary_search_by(|(key, _)| key.cmp(&name))
+        .ok()
+        .map(|idx| NAMED[idx].1.into())
+}
+
+// Sorted for binary search
+const NAMED: [(&str, u32); 148] = [
+    ("aliceblue", 0xF0F8FF),
+    ("antiquewhite", 0xFAEBD7),
+    ("aqua", 0x00FFFF),
+    ("aquamarine", 0x7FFFD4),
+    ("azure", 0xF0FFFF),
+    ("beige", 0xF5F5DC),
+    ("bisque", 0xFFE4C4),
+    ("black", 0x000000),
+    ("blanchedalmond", 0xFFEBCD),
+    ("blue", 0x0000FF),
+    ("blueviolet", 0x8A2BE2),
+    ("brown", 0xA52A2A),
+    ("burlywood", 0xDEB887),
+    ("cadetblue", 0x5F9EA0),
+    ("chartreuse", 0x7FFF00),
+    ("chocolate", 0xD2691E),
+    ("coral", 0xFF7F50),
+    ("cornflowerblue", 0x6495ED),
+    ("cornsilk", 0xFFF8DC),
+    ("crimson", 0xDC143C),
+    ("cyan", 0x00FFFF),
+    ("darkblue", 0x00008B),
+    ("darkcyan", 0x008B8B),
+    ("darkgoldenrod", 0xB8860B),
+    ("darkgray", 0xA9A9A9),
+    ("darkgreen", 0x006400),
+    ("darkgrey", 0xA9A9A9),
+    ("darkkhaki", 0xBDB76B),
+    ("darkmagenta", 0x8B008B),
+    ("darkolivegreen", 0x556B2F),
+    ("darkorange", 0xFF8C00),
+    ("darkorchid", 0x9932CC),
+    ("darkred", 0x8B0000),
+    ("darksalmon", 0xE9967A),
+    ("darkseagreen", 0x8FBC8F),
+    ("darkslateblue", 0x483D8B),
+    ("darkslategray", 0x2F4F4F),
+    ("darkslategrey", 0x2F4F4F),
+    ("darkturquoise", 0x00CED1),
+    ("darkviolet", 0x9400D3),
+    ("deeppink", 0xFF1493),
+    ("deepskyblue", 0x00BFFF),
+    ("dimgray", 0x696969),
+    ("dimgrey", 0x696969),
+    ("dodgerblue", 0x1E90FF),
+    ("firebrick", 0xB22222),
+    ("floralwhite", 0xFFFAF0),
+    ("forestgreen", 0x228B22),
+    ("fuchsia", 0xFF00FF),
+    ("gainsboro", 0xDCDCDC),
+    ("ghostwhite", 0xF8F8FF),
+    ("gold", 0xFFD700),
+    ("goldenrod", 0xDAA520),
+    ("gray", 0x808080),
+    ("green", 0x008000),
+    ("greenyellow", 0xADFF2F),
+    ("grey", 0x808080),
+    ("honeydew", 0xF0FFF0),
+    ("hotpink", 0xFF69B4),
+    ("indianred", 0xCD5C5C),
+    ("indigo", 0x4B0082),
+    ("ivory", 0xFFFFF0),
+    ("khaki", 0xF0E68C),
+    ("lavender", 0xE6E6FA),
+    ("lavenderblush", 0xFFF0F5),
+    ("lawngreen", 0x7CFC00),
+    ("lemonchiffon", 0xFFFACD),
+    ("lightblue", 0xADD8E6),
+    ("lightcoral", 0xF08080),
+    ("lightcyan", 0xE0FFFF),
+    ("lightgoldenrodyellow", 0xFAFAD2),
+    ("lightgray", 0xD3D3D3),
+    ("lightgreen", 0x90EE90),
+    ("lightgrey", 0xD3D3D3),
+    ("lightpink", 0xFFB6C1),
+    ("lightsalmon", 0xFFA07A),
+    ("lightseagreen", 0x20B2AA),
+    ("lightskyblue", 0x87CEFA),
+    ("lightslategray", 0x778899),
+    ("lightslategrey", 0x778899),
+    ("lightsteelblue", 0xB0C4DE),
+    ("lightyellow", 0xFFFFE0),
+    ("lime", 0x00FF00),
+    ("limegreen", 0x32CD32),
+    ("linen", 0xFAF0E6),
+    ("magenta", 0xFF00FF),
+    ("maroon", 0x800000),
+    ("mediumaquamarine", 0x66CDAA),
+    ("mediumblue", 0x0000CD),
+    ("mediumorchid", 0xBA55D3),
+    ("mediumpurple", 0x9370DB),
+    ("mediumseagreen", 0x3CB371),
+    ("mediumslateblue", 0x7B68EE),
+    ("mediumspringgreen", 0x00FA9A),
+    ("mediumturquoise", 0x48D1CC),
+    ("mediumvioletred", 0xC71585),
+    ("midnightblue", 0x191970),
+    ("mintcream", 0xF5FFFA),
+    ("mistyrose", 0xFFE4E1),
+    ("moccasin", 0xFFE4B5),
+    ("navajowhite", 0xFFDEAD),
+    ("navy", 0x000080),
+    ("oldlace", 0xFDF5E6),
+    ("olive", 0x808000),
+    ("olivedrab", 0x6B8E23),
+    ("orange", 0xFFA500),
+    ("orangered", 0xFF4500),
+    ("orchid", 0xDA70D6),
+    ("palegoldenrod", 0xEEE8AA),
+    ("palegreen", 0x98FB98),
+    ("paleturquoise", 0xAFEEEE),
+    ("palevioletred", 0xDB7093),
+    ("papayawhip", 0xFFEFD5),
+    ("peachpuff", 0xFFDAB9),
+    ("peru", 0xCD853F),
+    ("pink", 0xFFC0CB),
+    ("plum", 0xDDA0DD),
+    ("powderblue", 0xB0E0E6),
+    ("purple", 0x800080),
+    ("rebeccapurple", 0x663399),
+    ("red", 0xFF0000),
+    ("rosybrown", 0xBC8F8F),
+    ("royalblue", 0x4169E1),
+    ("saddlebrown", 0x8B4513),
+    ("salmon", 0xFA8072),
+    ("sandybrown", 0xF4A460),
+    ("seagreen", 0x2E8B57),
+    ("seashell", 0xFFF5EE),
+    ("sienna", 0xA0522D),
+    ("silver", 0xC0C0C0),
+    ("skyblue", 0x87CEEB),
+    ("slateblue", 0x6A5ACD),
+    ("slategray", 0x708090),
+    ("slategrey", 0x708090),
+    ("snow", 0xFFFAFA),
+    ("springgreen", 0x00FF7F),
+    ("steelblue", 0x4682B4),
+    ("tan", 0xD2B48C),
+    ("teal", 0x008080),
+    ("thistle", 0xD8BFD8),
+    ("tomato", 0xFF6347),
+    ("turquoise", 0x40E0D0),
+    ("violet", 0xEE82EE),
+    ("wheat", 0xF5DEB3),
+    ("white", 0xFFFFFF),
+    ("whitesmoke", 0xF5F5F5),
+    ("yellow", 0xFFFF00),
+    ("yellowgreen", 0x9ACD32),
+];
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_hex() {
+        assert_eq!("#f80".parse(), Ok(Color::new(255, 136, 0)));
+        assert_eq!("#BADA55".parse(), Ok(Color::from(0xBADA55)));
+        assert_eq!("#bada5580".parse(), Ok(Color(0xBA, 0xDA, 0x55, 0x80)));
+        assert!("#bada5".parse::<Color>().is_err());
+        assert!("#+fada5".parse::<Color>().is_err());
+    }
+
+    #[test]
+    fn parse_functions() {
+        assert_eq!("rgb(255, 0, 128)".parse(), Ok(Color::new(255, 0, 128)));
+        assert_eq!("rgb(100%, 0%, 0%)".parse(), Ok(Color::new(255, 0, 0)));
+        assert_eq!("rgba(0, 0, 255, 0.5)".parse(), Ok(Color(0, 0, 255, 128)));
+        assert_eq!("rgb(0 0 255 / 50%)".parse(), Ok(Color(0, 0, 255, 128)));
+        assert_eq!("hsl(120, 100%, 25%)".parse(), Ok(Color::new(0, 128, 0)));
+        assert_eq!(
+            "hsla(0deg, 100%, 50%, 1)".parse(),
+            Ok(Color::new(255, 0, 0))
+        );
+        assert!("rgb(1, 2)".parse::<Color>().is_err());
+        assert!("cmyk(1, 2, 3, 4)".parse::<Color>().is_err());
+    }
+
+    #[test]
+    fn parse_names() {
+        assert_eq!(" CornflowerBlue ".parse(), Ok(Color::from(0x6495ED)));
+        assert_eq!("transparent".parse(), Ok(Color(0, 0, 0, 0)));
+        assert!("notacolor".parse::<Color>().is_err());
+        assert!(NAMED.windows(2).all(|w| w[0].0 < w[1].0), "sorted names");
+    }
+
+    #[test]
+    fn display_roundtrip() {
+        for color in [Color::from(0xDEAD00), Color(1, 2, 3, 4)] {
+            assert_eq!(color.to_string().parse(), Ok(color));
+        }
+        assert_eq!(Color::from(0xBADA55).to_string(), "#bada55");
+    }
+
+    #[test]
+    fn hsl_hsv_roundtrip() {
+        for raw in [0xBADA55, 0x6495ED, 0xDEAD00, 0x808080, 0x000000] {
+            let color = Color::from(raw);
+            let (h, s, l) = color.to_hsl();
+            assert_eq!(Color::from_hsl(h, s, l), color, "hsl {:06x}", raw);
+            let (h, s, v) = color.to_hsv();
+            assert_eq!(Color::from_hsv(h, s, v), color, "hsv {:06x}", raw);
+        }
+    }
+
+    #[test]
+    fn adjust() {
+        let grey = Color::from(0x808080);
+        assert_eq!(Color::from(0x000000).lighten(1.0), Color::from(0xFFFFFF));
+        assert!(grey.darken(0.2).0 < grey.0);
+        assert_eq!(
+            Color::from(0x000000).mix(&Color::from(0xFFFFFF), 0.5),
+            Color::new(128, 128, 128)
+        );
+    }
+}
@@ -0,0 +1,309 @@
+//! Continuous color scales, mapping values to colors.
+
+use crate::Color;
+
+const VIRIDIS: [u32; 10] = [
+    0x440154, 0x482878, 0x3E4989, 0x31688E, 0x26828E, 0x1F9E89, 0x35B779, 0x6ECE58, 0xB5DE2B,
+    0xFDE725,
+];
+const MAGMA: [u32; 10] = [
+    0x000004, 0x180F3D, 0x440F76, 0x721F81, 0x9E2F7F, 0xCD4071, 0xF1605D, 0xFD9668, 0xFECA8D,
+    0xFCFDBF,
+];
+const INFERNO: [u32; 10] = [
+    0x000004, 0x1B0C41, 0x4A0C6B, 0x781C6D, 0xA52C60, 0xCF4446, 0xED6925, 0xFB9B06, 0xF7D13D,
+    0xFCFFA4,
+];
+const CIVIDIS: [u32; 10] = [
+    0x00224E, 0x123570, 0x3B496C, 0x575D6D, 0x707173, 0x8A8779, 0xA69D75, 0xC4B56C, 0xE4CF5B,
+    0xFEE838,
+];
+
+const CATEGORICAL: [u32; 10] = [
+    0x6495ED, 0xDEAD00, 0xBADA55, 0xE15759, 0xB07AA1, 0x76B7B2, 0xFF9DA7, 0x9C755F, 0xEDC948,
+    0xBAB0AC,
+];
+
+/// Distinct colors for telling series apart, repeating when exhausted
+#[derive(Debug, Clone)]
+pub struct Palette(Vec<Color>);
+
+impl Default for Palette {
+    fn default() -> Self {
+        Self::new(&CATEGORICAL)
+    }
+}
+
+impl Palette {
+    pub fn new(colors: &[u32]) -> Self {
+        assert!(!colors.is_empty(), "there has to be a color");
+        Self(colors.iter().map(|&raw| raw.into()).collect())
+    }
+
+    pub fn color(&self, idx: usize) -> Color {
+        self.0[idx % self.0.len()]
+    }
+}
+
+/// Color space used to blend between neighbouring stops
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub enum Interpolation {
+    Rgb,
+    /// CIE L*a*b*, perceptually uniform
+    Lab,
+}
+
+#[derive(Debug, Clone)]
+pub struct ColorScale {
+    stops: Vec<(f64, Color)>,
+    domain: Option<(f64, f64)>,
+    interpolation: Interpolation,
+}
+
+impl ColorScale {
+    /// Evenly spaced gradient through the given colors
+    pub fn new(colors: &[u32]) -> Self {
+        let last = (colors.len().max(2) - 1) as f64;
+        Self::from_stops(
+            colors
+                .iter()
+                .enumerate()
+                .map(|(i, &raw)| (i as f64 / last, raw.into()))
+                .collect(),
+        )
+    }
+
+    /// Custom gradient stops, positioned within 0..=1
+    pub fn from_stops(mut stops: Vec<(f64, Color)>) -> Self {
+        assert!(!stops.is_empty(), "there has to be a color stop");
+        stops.sort_by(|a, b| a.0.total_cmp(&b.0));
+        Self {
+            stops,
+            domain: None,
+            interpolation: Interpolation::Rgb,
+        }
+    }
+
+    pub fn viridis() -> Self {
+        Self::new(&VIRIDIS)
+    }
+
+    pub fn magma() -> Self {
+        Self::new(&MAGMA)
+    }
+
+    pub fn inferno() -> Self {
+        Self::new(&INFERNO)
+    }
+
+    pub fn cividis() -> Self {
+        Self::new(&CIVIDIS)
+    }
+
+    /// Single-hue ramp from low to high
+    pub fn sequential(from: u32, to: u32) -> Self {
+        Self::new(&[from, to]).with_interpolation(Interpolation::Lab)
+    }
+
+    /// Two ramps meeting at a neutral midpoint
+    pub fn diverging(low: u32, mid: u32, high: u32) -> Self {
+        Self::new(&[low, mid, high]).with_interpolation(Interpolation::Lab)
+    }
+
+    pub fn blues() -> Self {
+        Self::sequential(0xF7FBFF, 0x08306B)
+    }
+
+    pub fn greys() -> Self {
+        Self::sequential(0xFFFFFF, 0x000000)
+    }
+
+    pub fn red_blue() -> Self {
+        Self::diverging(0xB2182B, 0xF7F7F7, 0x2166AC)
+    }
+
+    pub fn with_interpolation(mut self, interpolation: Interpolation) -> Self {
+        self.interpolation = interpolation;
+        self
+    }
+
+    /// Value range mapped onto the gradient
+    pub fn with_domain(mut self, min: f64, max: f64) -> Self {
+        self.domain = Some((min, max));
+        self
+    }
+
+    /// Uses `range` as domain, unless one was set explicitly
+    pub fn fit(mut self, range: (f64, f64)) -> Self {
+        if self.domain.is_none() {
+            self.domain = Some(range);
+        }
+        self
+    }
+
+    pub fn domain(&self) -> (f64, f64) {
+        self.domain.unwrap_or((0.0, 1.0))
+    }
+
+    pub fn reversed(mut self) -> Self {
+        self.stops = self
+            .stops
+            .into_iter()
+            .rev()
+            .map(|(pos, color)| (1.0 - pos, color))
+            .collect();
+        self
+    }
+
+    /// Color for a value within domain, clamped at the ends
+    pub fn color(&self, value: f64) -> Color {
+        let (min, max) = self.domain();
+        if max == min {
+            return self.at(1.0);
+        }
+        self.at((value - min) / (max - min))
+    }
+
+    /// Color at normalized position `t`, clamped to 0..=1
+    pub fn at(&self, t: f64) -> Color {
+        let t = if t.is_nan() { 0.0 } else { t.clamp(0.0, 1.0) };
+        let upper = match self.stops.iter().position(|&(pos, _)| pos >= t) {
+            Some(0) => return self.stops[0].1,
+            Some(idx) => idx,
+            None => return self.stops[self.stops.len() - 1].1,
+        };
+        let (p0, c0) = self.stops[upper - 1];
+        let (p1, c1) = self.stops[upper];
+        let t = if p1 > p0 { (t - p0) / (p1 - p0) } else { 1.0 };
+        match self.interpolation {
+            Interpolation::Rgb => c0.mix(&c1, t),
+            Interpolation::Lab => {
+                let (l0, a0, b0) = to_lab(&c0);
+                let (l1, a1, b1) = to_lab(&c1);
+                from_lab(l0 + (l1 - l0) * t, a0 + (a1 - a0) * t, b0 + (b1 - b0) * t)
+                    .with_alpha(c0.mix(&c1, t).3)
+            }
+        }
+    }
+}
+
+// D65 reference white
+const WHITE: (f64, f64, f64) = (0.95047, 1.0, 1.08883);
+const DELTA: f64 = 6.0 / 29.0;
+
+fn to_linear(c: u8) -> f64 {
+    let c = c as f64 / 255.0;
+    if c <= 0.04045 {
+        c / 12.92
+    } else {
+        ((c + 0.055) / 1.055).powf(2.4)
+    }
+}
+
+fn from_linear(c: f64) -> u8 {
+    let c = if c <= 0.0031308 {
+        12.92 * c
+    } else {
+        1.055 * c.powf(1.0 / 2.4) - 0.055
+    };
+    (c.clamp(0.0, 1.0) * 255.0).round() as u8
+}
+
+fn to_lab(color: &Color) -> (f64, f64, f64) {
+    let (r, g, b) = (to_linear(color.0), to_linear(color.1), to_linear(color.2));
+    let x = 0.4124564 * r + 0.3575761 * g + 0.1804375 * b;
+    let y = 0.2126729 * r + 0.7151522 * g + 0.0721750 * b;
+    let z = 0.0193339 * r + 0.1191920 * g + 0.9503041 * b;
+    let f = |t: f64| {
+        if t > DELTA.powi(3) {
+            t.cbrt()
+        } else {
+            t / (3.0 * DELTA * DELTA) + 4.0 / 29.0
+        }
+    };
+    let (fx, fy, fz) = (f(x / WHITE.0), f(y / WHITE.1), f(z / WHITE.2));
+    (116.0 * fy - 16.0, 500.0 * (fx - fy), 200.0 * (fy - fz))
+}
+
+fn from_lab(l: f64, a: f64, b: f64) -> Color {
+    let fy = (l + 16.0) / 116.0;
+    let fx = fy + a / 500.0;
+    let fz = fy - b / 200.0;
+    let finv = |t: f64| {
+        if t > DELTA {
+            t.powi(3)
+        } else {
+            3.0 * DELTA * DELTA * (t - 4.0 / 29.0)
+        }
+    };
+    let (x, y, z) = (WHITE.0 * finv(fx), WHITE.1 * finv(fy), WHITE.2 * finv(fz));
+    let r = 3.2404542 * x - 1.5371385 * y - 0.4985314 * z;
+    let g = -0.9692660 * x + 1.8760108 * y + 0.0415560 * z;
+    let b = 0.0556434 * x - 0.2040259 * y + 1.0572252 * z;
+    Color::new(from_linear(r), from_linear(g), from_linear(b))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn ends_of_scale() {
+        let scale = ColorScale::viridis();
+        assert_eq!(scale.at(0.0), Color::from(0x440154));
+        assert_eq!(scale.at(1.0), Color::from(0xFDE725));
+        assert_eq!(scale.at(-3.0), scale.at(0.0), "clamped below");
+        assert_eq!(scale.at(3.0), scale.at(1.0), "clamped above");
+    }
+
+    #[test]
+    fn rgb_midpoint() {
+        let scale = ColorScale::new(&[0x000000, 0xFFFFFF]);
+        assert_eq!(scale.at(0.5), Color::new(128, 128, 128));
+    }
+
+    #[test]
+    fn lab_roundtrip() {
+        for raw in [0x000000, 0xFFFFFF, 0xBADA55, 0x6495ED, 0xDEAD00] {
+            let color = Color::from(raw);
+            let (l, a, b) = to_lab(&color);
+            assert_eq!(from_lab(l, a, b), color, "{:06x}", raw);
+        }
+    }
+
+    #[test]
+    fn lab_midpoint_is_perceptual() {
+        let scale = ColorScale::greys();
+        let mid = scale.at(0.5);
+        assert_eq!(mid.0, mid.1, "grey stays grey");
+        assert!(mid.0 > 110 && mid.0 < 125, "L* midpoint, got {}", mid.0);
+    }
+
+    #[test]
+    fn domain_maps_values() {
+        let scale = ColorScale::new(&[0x000000, 0xFFFFFF]).with_domain(0.0, 127.0);
+        assert_eq!(scale.color(0.0), Color::new(0, 0, 0));
+        assert_eq!(scale.color(127.0), Color::new(255, 255, 255));
+
+        let fitted = scale.clone().fit((10.0, 20.0));
+        assert_eq!(fitted.domain(), (0.0, 127.0), "explicit domain wins");
+    }
+
+    #[test]
+    fn palette_repeats() {
+        let palette = Palette::new(&[0x000000, 0xFFFFFF]);
+        assert_eq!(palette.color(1), Color::from(0xFFFFFF));
+        assert_eq!(palette.color(2), palette.color(0));
+    }
+
+    #[test]
+    fn custom_stops() {
+        let scale = ColorScale::from_stops(vec![
+            (1.0, Color::from(0x0000FF)),
+            (0.0, Color::from(0xFF0000)),
+            (0.25, Color::from(0x00FF00)),
+        ]);
+        assert_eq!(scale.at(0.25), Color::from(0x00FF00));
+        assert_eq!(scale.reversed().at(0.75), Color::from(0x00FF00));
+    }
+}
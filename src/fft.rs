@@ -0,0 +1,131 @@
+//! Radix-2 fast Fourier transform and window functions.
+
+use std::f64::consts::PI;
+
+/// Tapering applied to each frame before transforming
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub enum Window {
+    Rectangular,
+    Hann,
+    Hamming,
+    Blackman,
+}
+
+impl Window {
+    pub fn coefficients(&self, size: usize) -> Vec<f64> {
+        let last = (size.max(2) - 1) as f64;
+        (0..size)
+            .map(|n| {
+                let phase = 2.0 * PI * n as f64 / last;
+                match self {
+                    Window::Rectangular => 1.0,
+                    Window::Hann => 0.5 - 0.5 * phase.cos(),
+                    Window::Hamming => 0.54 - 0.46 * phase.cos(),
+                    Window::Blackman => 0.42 - 0.5 * phase.cos() + 0.08 * (2.0 * phase).cos(),
+                }
+            })
+            .collect()
+    }
+}
+
+/// In-place transform of complex input, length has to be a power of two
+pub fn fft(re: &mut [f64], im: &mut [f64]) {
+    let n = re.len();
+    assert!(n.is_power_of_two(), "fft size has to be a power of two");
+    assert_eq!(n, im.len(), "real and imaginary parts differ in length");
+
+    let mut j = 0;
+    for i in 1..n {
+        let mut bit = n >> 1;
+        while j & bit != 0 {
+            j ^= bit;
+            bit >>= 1;
+        }
+        j |= bit;
+        if i < j {
+            re.swap(i, j);
+            im.swap(i, j);
+        }
+    }
+
+    let mut len = 2;
+    while len <= n {
+        let angle = -2.0 * PI / len as f64;
+        let (wr, wi) = (angle.cos(), angle.sin());
+        for start in (0..n).step_by(len) {
+            let (mut cr, mut ci) = (1.0, 0.0);
+            for k in 0..len / 2 {
+                let (a, b) = (start + k, start + k + len / 2);
+                let tr = re[b] * cr - im[b] * ci;
+                let ti = re[b] * ci + im[b] * cr;
+                re[b] = re[a] - tr;
+                im[b] = im[a] - ti;
+                re[a] += tr;
+                im[a] += ti;
+                let next = cr * wr - ci * wi;
+                ci = cr * wi + ci * wr;
+                cr = next;
+            }
+        }
+        len <<= 1;
+    }
+}
+
+/// Amplitude of each bin up to Nyquist, a full-scale sine peaking at 1.
+/// Frame is zero-padded to the window length, rounded up to a power of two.
+pub fn spectrum(frame: &[f64], window: &[f64]) -> Vec<f64> {
+    let size = frame.len().max(window.len()).next_power_of_two();
+    let mut re = vec![0.0; size];
+    let mut im = vec![0.0; size];
+    for (i, (x, w)) in frame.iter().zip(window).enumerate() {
+        re[i] = x * w;
+    }
+    fft(&mut re, &mut im);
+    let gain = window.iter().sum::<f64>() / 2.0;
+    (0..=size / 2)
+        .map(|k| (re[k] * re[k] + im[k] * im[k]).sqrt() / gain)
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn impulse_is_flat() {
+        let mut re = vec![0.0; 8];
+        let mut im = vec![0.0; 8];
+        re[0] = 1.0;
+        fft(&mut re, &mut im);
+
+        assert!(re.iter().all(|&x| (x - 1.0).abs() < 1e-12));
+        assert!(im.iter().all(|&x| x.abs() < 1e-12));
+    }
+
+    #[test]
+    fn sine_peaks_at_its_bin() {
+        let size = 256;
+        let frame: Vec<f64> = (0..size)
+            .map(|n| (2.0 * PI * 16.0 * n as f64 / size as f64).sin())
+            .collect();
+        let bins = spectrum(&frame, &Window::Rectangular.coefficients(size));
+
+        assert_eq!(bins.len(), size / 2 + 1);
+        assert!(
+            (bins[16] - 1.0).abs() < 1e-9,
+            "full scale, got {}",
+            bins[16]
+        );
+        assert!(bins.iter().enumerate().all(|(k, &x)| k == 16 || x < 1e-9));
+    }
+
+    #[test]
+    fn window_shapes() {
+        let hann = Window::Hann.coefficients(5);
+        assert!(hann[0].abs() < 1e-12 && (hann[2] - 1.0).abs() < 1e-12);
+        let hamming = Window::Hamming.coefficients(5);
+        assert!((hamming[0] - 0.08).abs() < 1e-12);
+        let blackman = Window::Blackman.coefficients(5);
+        assert!(blackman[0].abs() < 1e-12 && (blackman[2] - 1.0).abs() < 1e-12);
+    }
+}
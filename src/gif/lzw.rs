@@ -0,0 +1,175 @@
+use std::collections::HashMap;
+
+/// Codes are at most 12 bits wide
+const MAX_CODE: u16 = 4095;
+
+/// Codes packed least significant bit first
+#[derive(Default)]
+struct Bits {
+    bytes: Vec<u8>,
+    pending: u32,
+    count: u8,
+}
+
+impl Bits {
+    fn write(&mut self, code: u16, width: u8) {
+        self.pending |= (code as u32) << self.count;
+        self.count += width;
+        while self.count >= 8 {
+            self.bytes.push(self.pending as u8);
+            self.pending >>= 8;
+            self.count -= 8;
+        }
+    }
+
+    fn finish(mut self) -> Vec<u8> {
+        if self.count > 0 {
+            self.bytes.push(self.pending as u8);
+        }
+        self.bytes
+    }
+}
+
+/// Variable width LZW of palette indices, as image data of a GIF
+pub(super) fn compress(indices: &[u8], min_size: u8) -> Vec<u8> {
+    let clear = 1u16 << min_size;
+    let end = clear + 1;
+    let mut bits = Bits::default();
+    let mut width = min_size + 1;
+    let mut next = end + 1;
+    let mut table: HashMap<(u16, u8), u16> = HashMap::new();
+    // Width grows once codes stop fitting, before the next entry is added
+    let emit = |bits: &mut Bits, code: u16, width: &mut u8, next: u16| {
+        bits.write(code, *width);
+        if next >= 1 << *width && *width < 12 {
+            *width += 1;
+        }
+    };
+
+    bits.write(clear, width);
+    let mut indices = indices.iter();
+    let mut prefix = match indices.next() {
+        Some(&index) => index as u16,
+        None => {
+            bits.write(end, width);
+            return bits.finish();
+        }
+    };
+    for &index in indices {
+        if let Some(&code) = table.get(&(prefix, index)) {
+            prefix = code;
+            continue;
+        }
+        emit(&mut bits, prefix, &mut width, next);
+        if next >= MAX_CODE {
+            bits.write(clear, width);
+            table.clear();
+            width = min_size + 1;
+            next = end + 1;
+        } else {
+            table.insert((prefix, index), next);
+            next += 1;
+        }
+        prefix = index as u16;
+    }
+    emit(&mut bits, prefix, &mut width, next);
+    bits.write(end, width);
+    bits.finish()
+}
+
+/// Data split into sub-blocks of at most 255 bytes, ending with an empty one
+pub(super) fn blocks(data: &[u8]) -> Vec<u8> {
+    let mut blocks = Vec::with_capacity(data.len() + data.len() / 255 + 2);
+    for chunk in data.chunks(255) {
+        blocks.push(chunk.len() as u8);
+        blocks.extend_from_slice(chunk);
+    }
+    blocks.push(0);
+    blocks
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Reference decoder, following the GIF specification
+    fn decompress(data: &[u8], min_size: u8) -> Vec<u8> {
+        let clear = 1u16 << min_size;
+        let end = clear + 1;
+        let mut table: Vec<Vec<u8>> = Vec::new();
+        let reset = |table: &mut Vec<Vec<u8>>| {
+            *table = (0..clear).map(|x| vec![x as u8]).collect();
+            table.push(Vec::new());
+            table.push(Vec::new());
+        };
+        reset(&mut table);
+        let (mut width, mut pos) = (min_size + 1, 0usize);
+        let mut previous: Option<Vec<u8>> = None;
+        let mut output = Vec::new();
+        loop {
+            let mut code = 0u16;
+            for bit in 0..width as usize {
+                let at = pos + bit;
+                code |= (((data[at / 8] >> (at % 8)) & 1) as u16) << bit;
+            }
+            pos += width as usize;
+            if code == clear {
+                reset(&mut table);
+                width = min_size + 1;
+                previous = None;
+                continue;
+            }
+            if code == end {
+                return output;
+            }
+            let entry = match (table.get(code as usize), &previous) {
+                (Some(entry), _) => entry.clone(),
+                (None, Some(previous)) => {
+                    let mut entry = previous.clone();
+                    entry.push(previous[0]);
+                    entry
+                }
+                (None, None) => panic!("code {} out of table", code),
+            };
+            if let Some(mut previous) = previous.take() {
+                previous.push(entry[0]);
+                table.push(previous);
+                if table.len() == 1 << width && width < 12 {
+                    width += 1;
+                }
+            }
+            output.extend_from_slice(&entry);
+            previous = Some(entry);
+        }
+    }
+
+    #[test]
+    fn roundtrips() {
+        let cases: Vec<(Vec<u8>, u8)> = vec![
+            (vec![], 2),
+            (vec![1, 1, 1, 1, 2, 2, 3, 0, 1, 1, 1, 1, 1, 1], 2),
+            (
+                (0..20000).map(|x| ((x * 7 + x / 13) % 256) as u8).collect(),
+                8,
+            ),
+            ((0..9000).map(|x| (x % 3) as u8).collect(), 2),
+        ];
+        for (indices, min_size) in cases {
+            let data = compress(&indices, min_size);
+            assert_eq!(
+                decompress(&data, min_size),
+                indices,
+                "min size {}",
+                min_size
+            );
+        }
+    }
+
+    #[test]
+    fn sub_blocks() {
+        let blocks = blocks(&[7; 300]);
+
+        assert_eq!(blocks.len(), 1 + 255 + 1 + 45 + 1);
+        assert_eq!((blocks[0], blocks[256], blocks[302]), (255, 45, 0));
+    }
+}
@@ -0,0 +1,173 @@
+//! Animated GIF, for short looping previews.
+
+use crate::animation::Frames;
+use crate::Dimension;
+use rsound_output::*;
+
+mod lzw;
+mod quantize;
+
+/// Frames of a GIF, each with its own palette of at most 256 colors
+pub struct Clip {
+    size: Dimension,
+    /// Hundredths of a second each frame is shown
+    delay: u16,
+    /// Times played after the first, forever when zero and once when missing
+    repeat: Option<u16>,
+    dithering: bool,
+    frames: usize,
+    buffer: Vec<u8>,
+}
+
+impl Clip {
+    pub fn new(size: &Dimension) -> Self {
+        assert!(
+            size.width() <= u16::MAX as f64 && size.height() <= u16::MAX as f64,
+            "GIF sides are at most 65535 pixels"
+        );
+        Self {
+            size: *size,
+            delay: 10,
+            repeat: Some(0),
+            dithering: false,
+            frames: 0,
+            buffer: Vec::new(),
+        }
+    }
+
+    /// Delay between frames closest to the frame rate, GIF counting in hundredths
+    pub fn with_fps(mut self, fps: f64) -> Self {
+        self.delay = (100.0 / fps).round().clamp(1.0, u16::MAX as f64) as u16;
+        self
+    }
+
+    /// Delay of frames pushed without one, in hundredths of a second
+    pub fn with_delay(mut self, delay: u16) -> Self {
+        self.delay = delay;
+        self
+    }
+
+    /// Plays `count` more times after the first, instead of looping forever
+    pub fn with_repeat(mut self, count: u16) -> Self {
+        self.repeat = if count == 0 { None } else { Some(count) };
+        self
+    }
+
+    /// Spreads the error of reducing colors onto neighbouring pixels
+    pub fn with_dithering(mut self) -> Self {
+        self.dithering = true;
+        self
+    }
+
+    pub fn frames(&self) -> usize {
+        self.frames
+    }
+
+    /// Frame of the clip's size as packed RGB, shown for `delay` hundredths of a second
+    pub fn push_with_delay(&mut self, rgb: &[u8], delay: u16) {
+        let (width, height) = (self.size.width() as usize, self.size.height() as usize);
+        assert_eq!(
+            rgb.len(),
+            width * height * 3,
+            "frame has to be the size of the clip"
+        );
+        let palette = quantize::palette(rgb, 256);
+        let indices = if self.dithering {
+            quantize::dither(rgb, width, &palette)
+        } else {
+            quantize::map(rgb, &palette)
+        };
+        // Color tables hold a power of two entries, two at least
+        let bits = (palette.len().max(2) as f64).log2().ceil() as u8;
+
+        // Graphic control extension, keeping each frame in place for the next
+        self.buffer.extend_from_slice(&[0x21, 0xF9, 0x04, 0x04]);
+        self.buffer.extend_from_slice(&delay.to_le_bytes());
+        self.buffer.extend_from_slice(&[0x00, 0x00]);
+        // Image descriptor with a local color table
+        self.buffer.push(0x2C);
+        for value in [0, 0, width as u16, height as u16] {
+            self.buffer.extend_from_slice(&value.to_le_bytes());
+        }
+        self.buffer.push(0x80 | (bits - 1));
+        for idx in 0..1 << bits {
+            self.buffer
+                .extend_from_slice(&palette.get(idx).copied().unwrap_or([0, 0, 0]));
+        }
+        let min_size = bits.max(2);
+        self.buffer.push(min_size);
+        self.buffer
+            .append(&mut lzw::blocks(&lzw::compress(&indices, min_size)));
+        self.frames += 1;
+    }
+}
+
+impl Frames for Clip {
+    fn push(&mut self, rgb: &[u8]) {
+        self.push_with_delay(rgb, self.delay);
+    }
+}
+
+impl Buffer for Clip {
+    fn get_buffer(&self) -> &[u8] {
+        &self.buffer
+    }
+}
+
+impl OutputRenderer for Clip {
+    fn get_header(&self) -> Option<Vec<u8>> {
+        let mut header = b"GIF89a".to_vec();
+        header.extend_from_slice(&(self.size.width() as u16).to_le_bytes());
+        header.extend_from_slice(&(self.size.height() as u16).to_le_bytes());
+        // No global color table, frames bring their own
+        header.extend_from_slice(&[0x00, 0x00, 0x00]);
+        if let Some(count) = self.repeat {
+            header.extend_from_slice(b"\x21\xFF\x0BNETSCAPE2.0\x03\x01");
+            header.extend_from_slice(&count.to_le_bytes());
+            header.push(0x00);
+        }
+        Some(header)
+    }
+
+    fn get_footer(&self) -> Option<Vec<u8>> {
+        Some(vec![0x3B])
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn frames_with_delays() {
+        let mut clip = Clip::new(&Dimension::new(2.0, 2.0)).with_fps(25.0);
+        clip.push(&[255, 0, 0, 0, 255, 0, 0, 0, 255, 255, 0, 0]);
+        let second = clip.get_buffer().len();
+        clip.push_with_delay(&[0; 12], 100);
+        let buffer = clip.get_buffer();
+
+        assert_eq!(clip.frames(), 2);
+        assert_eq!(&buffer[..8], &[0x21, 0xF9, 0x04, 0x04, 4, 0, 0, 0]);
+        assert_eq!(buffer[17], 0x80 | 1, "four entry color table");
+        assert_eq!(&buffer[18..21], &[0, 0, 255]);
+        assert_eq!(&buffer[second + 4..second + 6], &[100, 0]);
+    }
+
+    #[test]
+    fn header_loops() {
+        let clip = Clip::new(&Dimension::new(300.0, 20.0));
+        let header = clip.get_header().unwrap();
+
+        assert_eq!(&header[..10], b"GIF89a\x2C\x01\x14\x00");
+        assert_eq!(&header[header.len() - 4..], &[0x01, 0x00, 0x00, 0x00]);
+        assert_eq!(
+            clip.with_repeat(0).get_header().unwrap().len(),
+            13,
+            "plays once"
+        );
+        assert_eq!(
+            Clip::new(&Dimension::new(1.0, 1.0)).get_footer(),
+            Some(vec![0x3B])
+        );
+    }
+}
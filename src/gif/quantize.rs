@@ -0,0 +1,181 @@
+use std::collections::HashMap;
+
+type Rgb = [u8; 3];
+
+/// Colors with how often they occur
+type Bucket = Vec<(Rgb, u32)>;
+
+/// At most `colors` colors standing in for the pixels, by median cut.
+/// Frames with few enough colors keep them exactly.
+pub(super) fn palette(rgb: &[u8], colors: usize) -> Vec<Rgb> {
+    let mut counts: HashMap<Rgb, u32> = HashMap::new();
+    for pixel in rgb.chunks_exact(3) {
+        *counts.entry([pixel[0], pixel[1], pixel[2]]).or_insert(0) += 1;
+    }
+    let mut histogram: Bucket = counts.into_iter().collect();
+    histogram.sort_unstable();
+    if histogram.len() <= colors {
+        return histogram.into_iter().map(|(color, _)| color).collect();
+    }
+    let mut buckets = vec![histogram];
+    while buckets.len() < colors {
+        // Widest bucket along any channel, weighed by how many pixels it holds
+        let widest = buckets
+            .iter()
+            .enumerate()
+            .filter(|(_, bucket)| bucket.len() > 1)
+            .map(|(idx, bucket)| {
+                let (channel, range) = widest(bucket);
+                let pixels: u32 = bucket.iter().map(|x| x.1).sum();
+                (idx, channel, range as u64 * pixels as u64)
+            })
+            .max_by_key(|x| x.2);
+        let (idx, channel, _) = match widest {
+            Some(widest) => widest,
+            None => break,
+        };
+        let mut bucket = buckets.swap_remove(idx);
+        bucket.sort_unstable_by_key(|x| x.0[channel]);
+        let half = bucket.iter().map(|x| x.1 as u64).sum::<u64>() / 2;
+        let mut seen = 0;
+        let split = bucket
+            .iter()
+            .position(|x| {
+                seen += x.1 as u64;
+                seen > half
+            })
+            .unwrap_or(0)
+            .clamp(1, bucket.len() - 1);
+        let upper = bucket.split_off(split);
+        buckets.push(bucket);
+        buckets.push(upper);
+    }
+    buckets.iter().map(average).collect()
+}
+
+/// Channel with the largest spread of values, and that spread
+fn widest(bucket: &Bucket) -> (usize, u8) {
+    (0..3)
+        .map(|channel| {
+            let (lo, hi) = bucket.iter().fold((u8::MAX, u8::MIN), |(lo, hi), x| {
+                (lo.min(x.0[channel]), hi.max(x.0[channel]))
+            });
+            (channel, hi - lo)
+        })
+        .max_by_key(|x| x.1)
+        .unwrap_or((0, 0))
+}
+
+fn average(bucket: &Bucket) -> Rgb {
+    let pixels: u64 = bucket.iter().map(|x| x.1 as u64).sum::<u64>().max(1);
+    let mut color = [0; 3];
+    for (channel, value) in color.iter_mut().enumerate() {
+        let total: u64 = bucket
+            .iter()
+            .map(|x| x.0[channel] as u64 * x.1 as u64)
+            .sum();
+        *value = ((total + pixels / 2) / pixels) as u8;
+    }
+    color
+}
+
+/// Palette entry closest to the color
+fn nearest(palette: &[Rgb], color: [i32; 3]) -> u8 {
+    palette
+        .iter()
+        .enumerate()
+        .min_by_key(|(_, entry)| {
+            (0..3)
+                .map(|c| (entry[c] as i32 - color[c]).pow(2))
+                .sum::<i32>()
+        })
+        .map_or(0, |(idx, _)| idx as u8)
+}
+
+/// Palette index of every pixel
+pub(super) fn map(rgb: &[u8], palette: &[Rgb]) -> Vec<u8> {
+    let mut cache: HashMap<Rgb, u8> = HashMap::new();
+    rgb.chunks_exact(3)
+        .map(|pixel| {
+            let color = [pixel[0], pixel[1], pixel[2]];
+            *cache
+                .entry(color)
+                .or_insert_with(|| nearest(palette, color.map(|x| x as i32)))
+        })
+        .collect()
+}
+
+/// Palette index of every pixel, spreading the error of each onto its
+/// neighbours by Floyd-Steinberg
+pub(super) fn dither(rgb: &[u8], width: usize, palette: &[Rgb]) -> Vec<u8> {
+    let height = rgb.len() / 3 / width.max(1);
+    let mut error = vec![[0i32; 3]; width * 2];
+    let mut indices = Vec::with_capacity(width * height);
+    for y in 0..height {
+        let (current, below) = error.split_at_mut(width);
+        for x in 0..width {
+            let offset = (y * width + x) * 3;
+            let mut color = [0; 3];
+            for c in 0..3 {
+                color[c] = (rgb[offset + c] as i32 + current[x][c] / 16).clamp(0, 255);
+            }
+            let index = nearest(palette, color);
+            indices.push(index);
+            for c in 0..3 {
+                let delta = color[c] - palette[index as usize][c] as i32;
+                if x + 1 < width {
+                    current[x + 1][c] += delta * 7;
+                    below[x + 1][c] += delta;
+                }
+                if x > 0 {
+                    below[x - 1][c] += delta * 3;
+                }
+                below[x][c] += delta * 5;
+            }
+        }
+        error.copy_within(width.., 0);
+        error[width..].fill([0; 3]);
+    }
+    indices
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn median_cut_limits_colors() {
+        let few = [255, 0, 0, 0, 0, 255, 255, 0, 0];
+        let many: Vec<u8> = (0..4096u32)
+            .flat_map(|x| {
+                [
+                    (x % 16 * 16) as u8,
+                    (x / 16 % 16 * 16) as u8,
+                    (x / 256 * 16) as u8,
+                ]
+            })
+            .collect();
+
+        assert_eq!(palette(&few, 256), vec![[0, 0, 255], [255, 0, 0]], "exact");
+        assert_eq!(palette(&many, 64).len(), 64);
+        assert_eq!(map(&few, &palette(&few, 256)), vec![1, 0, 1]);
+    }
+
+    #[test]
+    fn dithering_keeps_average() {
+        let gray: Vec<u8> = vec![128; 16 * 16 * 3];
+        let palette = [[0, 0, 0], [255, 255, 255]];
+        let indices = dither(&gray, 16, &palette);
+        let white = indices.iter().filter(|&&x| x == 1).count();
+
+        assert!(
+            map(&gray, &palette).iter().all(|&x| x == 1),
+            "plain mapping rounds up"
+        );
+        assert!(
+            (white as i32 - 128).abs() < 16,
+            "about half white, got {}",
+            white
+        );
+    }
+}
@@ -0,0 +1,328 @@
+use super::{Graph, Plot, CHAR_HEIGHT, CHAR_WIDTH};
+use crate::{Block, Color, Dimension, Point, Renderable, ShapeRenderer, Style};
+
+#[derive(Debug, Copy, Clone, PartialEq)]
+enum Kind {
+    /// Vertical line at a time
+    Marker(f64),
+    /// Shaded span between two times
+    Region(f64, f64),
+    /// Horizontal line at a value
+    Reference(f64),
+    /// Text off a point, with a leader line to it
+    Callout(f64, f64),
+}
+
+/// Note on a plot, placed by time and value
+#[derive(Debug, Clone, PartialEq)]
+pub struct Annotation {
+    kind: Kind,
+    text: String,
+    color: Color,
+}
+
+impl Annotation {
+    /// Loop points, cues, anything happening at a time
+    pub fn marker(time: f64, text: &str) -> Self {
+        Self::new(Kind::Marker(time), text)
+    }
+
+    /// Sections, labelled at the top
+    pub fn region(from: f64, to: f64, text: &str) -> Self {
+        Self::new(Kind::Region(from.min(to), from.max(to)), text)
+    }
+
+    /// Threshold levels, labelled at the right
+    pub fn reference(value: f64, text: &str) -> Self {
+        Self::new(Kind::Reference(value), text)
+    }
+
+    pub fn callout(time: f64, value: f64, text: &str) -> Self {
+        Self::new(Kind::Callout(time, value), text)
+    }
+
+    fn new(kind: Kind, text: &str) -> Self {
+        Self {
+            kind,
+            text: text.to_string(),
+            color: Color::from(0xFF7F50),
+        }
+    }
+
+    pub fn with_color(mut self, color: u32) -> Self {
+        self.color = color.into();
+        self
+    }
+
+    /// Nothing when it lies outside the plot area, regions cut at its edges
+    fn renderables<G: Plot>(&self, graph: &G) -> Vec<Renderable> {
+        let (area, size) = graph.area();
+        let (right, bottom) = (area.x + size.w, area.y + size.h);
+        let (across, down) = (area.x..=right, area.y..=bottom);
+        let line = Style::from(self.color);
+        let text = Style::from(self.color);
+        let width = self.text.chars().count() as f64 * CHAR_WIDTH;
+        let x = |time: f64| graph.position(time, 0.0).x;
+        let y = |value: f64| graph.position(0.0, value).y;
+        match self.kind {
+            Kind::Marker(time) => {
+                let x = x(time);
+                if !across.contains(&x) {
+                    return Vec::new();
+                }
+                vec![
+                    Renderable::Rect(
+                        Point { x, y: area.y },
+                        Dimension { w: 1.0, h: size.h },
+                        line,
+                    ),
+                    Renderable::Text(
+                        Point {
+                            x: x + CHAR_WIDTH / 2.0,
+                            y: area.y + CHAR_HEIGHT / 2.0,
+                        },
+                        self.text.clone(),
+                        text,
+                    ),
+                ]
+            }
+            Kind::Region(from, to) => {
+                let (from, to) = (x(from), x(to));
+                if to < area.x || from > right {
+                    return Vec::new();
+                }
+                let (from, to) = (from.max(area.x), to.min(right));
+                vec![
+                    Renderable::Rect(
+                        Point { x: from, y: area.y },
+                        Dimension {
+                            w: to - from,
+                            h: size.h,
+                        },
+                        Style::from(self.color.with_alpha(48)),
+                    ),
+                    Renderable::Text(
+                        Point {
+                            x: from + ((to - from - width) / 2.0).max(0.0),
+                            y: area.y + CHAR_HEIGHT / 2.0,
+                        },
+                        self.text.clone(),
+                        text,
+                    ),
+                ]
+            }
+            Kind::Reference(value) => {
+                let y = y(value);
+                if !down.contains(&y) {
+                    return Vec::new();
+                }
+                vec![
+                    Renderable::Line(
+                        Point { x: area.x, y },
+                        Point { x: right, y },
+                        line.with_border(1.0).with_dash(4.0, 4.0),
+                    ),
+                    Renderable::Text(
+                        Point {
+                            x: right - width,
+                            y: y - CHAR_HEIGHT * 1.5,
+                        },
+                        self.text.clone(),
+                        text,
+                    ),
+                ]
+            }
+            Kind::Callout(time, value) => {
+                let at = graph.position(time, value);
+                if !across.contains(&at.x) || !down.contains(&at.y) {
+                    return Vec::new();
+                }
+                // Leans towards the middle, staying inside the plot area
+                let dx = if at.x + width + CHAR_WIDTH * 3.0 > right {
+                    -(width + CHAR_WIDTH * 3.0)
+                } else {
+                    CHAR_WIDTH * 3.0
+                };
+                let dy = if at.y - CHAR_HEIGHT * 4.0 < area.y {
+                    CHAR_HEIGHT * 3.0
+                } else {
+                    -CHAR_HEIGHT * 4.0
+                };
+                let end = Point {
+                    x: at.x + dx.signum() * CHAR_WIDTH * 2.0,
+                    y: at.y + dy + CHAR_HEIGHT / 2.0,
+                };
+                vec![
+                    Renderable::Line(at, end, line.with_border(1.0)),
+                    Renderable::Text(
+                        Point {
+                            x: at.x + dx,
+                            y: at.y + dy,
+                        },
+                        self.text.clone(),
+                        text,
+                    ),
+                ]
+            }
+        }
+    }
+}
+
+/// Graph with annotations drawn over its data, or beneath it
+pub struct Annotated<G> {
+    graph: G,
+    annotations: Vec<Annotation>,
+    beneath: bool,
+}
+
+impl<G: Plot> Annotated<G> {
+    pub fn new(graph: G) -> Self {
+        Self {
+            graph,
+            annotations: Vec::new(),
+            beneath: false,
+        }
+    }
+
+    pub fn with_annotation(mut self, annotation: Annotation) -> Self {
+        self.annotations.push(annotation);
+        self
+    }
+
+    /// Draws annotations right over the grid, with the data on top
+    pub fn with_beneath(mut self) -> Self {
+        self.beneath = true;
+        self
+    }
+
+    pub fn graph(&self) -> &G {
+        &self.graph
+    }
+}
+
+impl<G: Plot> Graph for Annotated<G> {
+    fn size(&self) -> &Dimension {
+        self.graph.size()
+    }
+
+    fn base(&self) -> &Block {
+        self.graph.base()
+    }
+
+    fn margin(&self) -> &Dimension {
+        self.graph.margin()
+    }
+
+    fn padding(&self) -> &Block {
+        self.graph.padding()
+    }
+
+    fn draw<T>(&self, renderer: &mut T) -> Vec<u8>
+    where
+        T: ShapeRenderer,
+    {
+        for rect in self.renderables() {
+            renderer.draw(rect);
+        }
+
+        renderer.get_buffer().to_vec()
+    }
+
+    fn renderables(&self) -> Vec<Renderable> {
+        let mut renderables = self.graph.renderables();
+        let annotations = self
+            .annotations
+            .iter()
+            .flat_map(|x| x.renderables(&self.graph));
+        if self.beneath {
+            let at = self.graph.data_start().min(renderables.len());
+            renderables.splice(at..at, annotations);
+        } else {
+            renderables.extend(annotations);
+        }
+        renderables
+    }
+}
+
+impl<G: Plot> Plot for Annotated<G> {
+    fn position(&self, time: f64, value: f64) -> Point {
+        self.graph.position(time, value)
+    }
+
+    fn area(&self) -> (Point, Dimension) {
+        self.graph.area()
+    }
+
+    fn data_start(&self) -> usize {
+        self.graph.data_start()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{Hits, Line};
+
+    #[test]
+    fn placed_by_time_and_value() {
+        let blocks = [Block(4.0, 1.0), Block(4.0, 3.0), Block(4.0, 2.0)];
+        let graph = Annotated::new(Hits::new(&blocks))
+            .with_annotation(Annotation::marker(4.0, "loop"))
+            .with_annotation(Annotation::region(4.0, 8.0, "chorus"))
+            .with_annotation(Annotation::reference(2.0, "limit"));
+        let renderables = graph.renderables();
+        let annotations = &renderables[renderables.len() - 6..];
+
+        match (&annotations[0], &annotations[2], &annotations[4]) {
+            (
+                Renderable::Rect(marker, _, _),
+                Renderable::Rect(region, span, _),
+                Renderable::Line(from, to, _),
+            ) => {
+                assert_eq!(marker.x, 40.0 + 4.0 * 20.0);
+                assert_eq!((region.x, span.w), (40.0 + 80.0, 80.0));
+                assert_eq!((from.y, to.y), (40.0 + 2.0 * 20.0, 40.0 + 2.0 * 20.0));
+            }
+            _ => panic!("expected annotations in order"),
+        }
+    }
+
+    #[test]
+    fn beneath_data() {
+        let blocks = [Block(4.0, 1.0), Block(4.0, 3.0)];
+        let line = Line::new(&blocks);
+        let count = line.renderables().len();
+        let grid = line.grid().len();
+        let graph = Annotated::new(line)
+            .with_annotation(Annotation::callout(8.0, 3.0, "peak"))
+            .with_beneath();
+        let renderables = graph.renderables();
+
+        assert_eq!(renderables.len(), count + 2);
+        match &renderables[grid + 1] {
+            Renderable::Text(_, text, _) => assert_eq!(text, "peak"),
+            _ => panic!("expected callout right after the grid"),
+        }
+    }
+
+    #[test]
+    fn outside_area_skipped() {
+        let blocks = [Block(4.0, 1.0), Block(4.0, 3.0)];
+        let hits = Hits::new(&blocks);
+        let count = hits.renderables().len();
+        let graph = Annotated::new(hits)
+            .with_annotation(Annotation::marker(20.0, "later"))
+            .with_annotation(Annotation::reference(10.0, "high"))
+            .with_annotation(Annotation::callout(2.0, -5.0, "low"))
+            .with_annotation(Annotation::region(6.0, 12.0, "outro"));
+        let renderables = graph.renderables();
+
+        assert_eq!(renderables.len(), count + 2, "only the region");
+        match &renderables[count] {
+            Renderable::Rect(region, span, _) => {
+                assert_eq!((region.x, span.w), (40.0 + 120.0, 40.0), "cut at the edge");
+            }
+            _ => panic!("expected region"),
+        }
+    }
+}
@@ -0,0 +1,400 @@
+use super::{Graph, LegendPosition, Line, Plot, Scalable, Series, Viewport};
+use crate::colormap::Palette;
+use crate::{Block, Dimension, Meta, Point, Renderable, ShapeRenderer, Style};
+
+/// Height in base units the columns of a normalized stack add up to
+const NORMALIZED: f64 = 4.0;
+
+/// Closed outline between an upper and a lower edge
+fn band(upper: &[Point], lower: &[Point]) -> Vec<Point> {
+    upper.iter().chain(lower.iter().rev()).cloned().collect()
+}
+
+/// Upper edge dropped onto the given vertical position
+fn floor(upper: &[Point], y: f64) -> Vec<Point> {
+    upper.iter().map(|p| Point { x: p.x, y }).collect()
+}
+
+/// Fill of a series, tagged with its name
+fn fill(line: &Line, idx: usize) -> Meta {
+    Meta::class("fill").with_data("series", line.name(idx))
+}
+
+/// Line filled down to the baseline, series overlapping each other
+pub struct Area<'a> {
+    line: Line<'a>,
+    opacity: u8,
+}
+
+impl<'a> Area<'a> {
+    pub fn new(blocks: &'a [Block]) -> Self {
+        Self::multi(vec![Series::new("", blocks)])
+    }
+
+    pub fn multi(series: Vec<Series<'a>>) -> Self {
+        Self {
+            line: Line::multi(series),
+            opacity: 96,
+        }
+    }
+
+    pub fn with_palette(mut self, palette: Palette) -> Self {
+        self.line = self.line.with_palette(palette);
+        self
+    }
+
+    pub fn with_legend(mut self, position: LegendPosition) -> Self {
+        self.line = self.line.with_legend(position);
+        self
+    }
+
+    /// Value the fill reaches down, or up, to
+    pub fn with_baseline(mut self, baseline: f64) -> Self {
+        self.line = self.line.with_baseline(baseline);
+        self
+    }
+
+    /// Alpha of the fill, the line on top stays opaque
+    pub fn with_opacity(mut self, opacity: u8) -> Self {
+        self.opacity = opacity;
+        self
+    }
+
+    pub fn with_viewport(mut self, viewport: Viewport) -> Self {
+        self.line = self.line.with_viewport(viewport);
+        self
+    }
+}
+
+impl<'a> Scalable for Area<'a> {
+    fn with_base(mut self, base: Block) -> Self {
+        self.line = self.line.with_base(base);
+        self
+    }
+}
+
+impl<'a> Plot for Area<'a> {
+    fn position(&self, time: f64, value: f64) -> Point {
+        self.line.position(time, value)
+    }
+}
+
+impl<'a> Graph for Area<'a> {
+    fn size(&self) -> &Dimension {
+        self.line.size()
+    }
+
+    fn base(&self) -> &Block {
+        self.line.base()
+    }
+
+    fn margin(&self) -> &Dimension {
+        self.line.margin()
+    }
+
+    fn draw<T>(&self, renderer: &mut T) -> Vec<u8>
+    where
+        T: ShapeRenderer,
+    {
+        for rect in self.renderables() {
+            renderer.draw(rect);
+        }
+
+        renderer.get_buffer().to_vec()
+    }
+
+    fn renderables(&self) -> Vec<Renderable> {
+        let baseline = self.line.baseline_y();
+        let fills: Vec<Renderable> = self
+            .line
+            .series_edges()
+            .iter()
+            .enumerate()
+            .map(|(idx, upper)| {
+                Renderable::Polygon(
+                    band(upper, &floor(upper, baseline)),
+                    Style::from(self.line.color(idx).with_alpha(self.opacity)),
+                )
+                .with_meta(fill(&self.line, idx))
+            })
+            .collect();
+        self.line.renderables_over(fills)
+    }
+}
+
+/// Series piled on top of each other, each filling down to the one below.
+/// Columns follow the durations of the longest series.
+pub struct StackedArea<'a> {
+    size: Dimension,
+    margin: Dimension,
+    base: Block,
+    series: Vec<Series<'a>>,
+    layers: Vec<Vec<Block>>,
+    palette: Palette,
+    legend: Option<LegendPosition>,
+    normalized: bool,
+    opacity: u8,
+    viewport: Option<Viewport>,
+}
+
+impl<'a> StackedArea<'a> {
+    pub fn new(series: Vec<Series<'a>>) -> Self {
+        let mut stack = Self {
+            size: Dimension { w: 0.0, h: 0.0 },
+            margin: Dimension { w: 40.0, h: 40.0 },
+            base: Block(20.0, 20.0),
+            series,
+            layers: Vec::new(),
+            palette: Palette::default(),
+            legend: None,
+            normalized: false,
+            opacity: 160,
+            viewport: None,
+        };
+        stack.resize();
+        stack
+    }
+
+    pub fn with_palette(mut self, palette: Palette) -> Self {
+        self.palette = palette;
+        self
+    }
+
+    pub fn with_legend(mut self, position: LegendPosition) -> Self {
+        self.legend = Some(position);
+        self.resize();
+        self
+    }
+
+    /// Shows shares of each column's total instead of values
+    pub fn with_normalization(mut self) -> Self {
+        self.normalized = true;
+        self.resize();
+        self
+    }
+
+    pub fn with_opacity(mut self, opacity: u8) -> Self {
+        self.opacity = opacity;
+        self
+    }
+
+    /// Stacks the whole piece, then draws the part within the window
+    pub fn with_viewport(mut self, viewport: Viewport) -> Self {
+        self.viewport = Some(viewport);
+        self.resize();
+        self
+    }
+
+    /// Running totals per column, one layer per series
+    fn stack(&self) -> Vec<Vec<Block>> {
+        let longest = self
+            .series
+            .iter()
+            .map(|x| x.blocks)
+            .max_by_key(|x| x.len())
+            .unwrap_or(&[]);
+        let mut totals = vec![0.0; longest.len()];
+        let mut layers: Vec<Vec<Block>> = self
+            .series
+            .iter()
+            .map(|series| {
+                longest
+                    .iter()
+                    .enumerate()
+                    .map(|(idx, column)| {
+                        totals[idx] += series.blocks.get(idx).map_or(0.0, |x| x.1.max(0.0));
+                        Block(column.0, totals[idx])
+                    })
+                    .collect()
+            })
+            .collect();
+        if self.normalized {
+            for layer in layers.iter_mut() {
+                for (block, total) in layer.iter_mut().zip(&totals) {
+                    if *total > 0.0 {
+                        block.1 *= NORMALIZED / total;
+                    }
+                }
+            }
+        }
+        layers
+    }
+
+    fn line(&self) -> Line<'_> {
+        let series = self
+            .series
+            .iter()
+            .zip(&self.layers)
+            .map(|(series, layer)| series.with_blocks(layer))
+            .collect();
+        let line = Line::multi(series)
+            .with_palette(self.palette.clone())
+            .with_base(Block(self.base.0, self.base.1));
+        let line = match self.legend {
+            Some(position) => line.with_legend(position),
+            None => line,
+        };
+        match self.viewport {
+            Some(viewport) => line.with_viewport(viewport),
+            None => line,
+        }
+    }
+
+    fn resize(&mut self) {
+        self.layers = self.stack();
+        let line = self.line();
+        let (size, margin) = (*line.size(), *line.margin());
+        self.size = size;
+        self.margin = margin;
+    }
+}
+
+impl<'a> Scalable for StackedArea<'a> {
+    fn with_base(mut self, base: Block) -> Self {
+        self.base = base;
+        self.resize();
+        self
+    }
+}
+
+impl<'a> Plot for StackedArea<'a> {
+    /// Value as a height on the stack, normalized or not
+    fn position(&self, time: f64, value: f64) -> Point {
+        self.line().position(time, value)
+    }
+}
+
+impl<'a> Graph for StackedArea<'a> {
+    fn size(&self) -> &Dimension {
+        &self.size
+    }
+
+    fn base(&self) -> &Block {
+        &self.base
+    }
+
+    fn margin(&self) -> &Dimension {
+        &self.margin
+    }
+
+    fn draw<T>(&self, renderer: &mut T) -> Vec<u8>
+    where
+        T: ShapeRenderer,
+    {
+        for rect in self.renderables() {
+            renderer.draw(rect);
+        }
+
+        renderer.get_buffer().to_vec()
+    }
+
+    fn renderables(&self) -> Vec<Renderable> {
+        let line = self.line();
+        let edges = line.series_edges();
+        let fills: Vec<Renderable> = edges
+            .iter()
+            .enumerate()
+            .map(|(idx, upper)| {
+                let lower = match idx {
+                    0 => floor(upper, line.baseline_y()),
+                    _ => edges[idx - 1].clone(),
+                };
+                Renderable::Polygon(
+                    band(upper, &lower),
+                    Style::from(line.color(idx).with_alpha(self.opacity)),
+                )
+                .with_meta(fill(&line, idx))
+            })
+            .collect();
+        line.renderables_over(fills)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::graph::data;
+    use crate::Interpolation;
+
+    /// Outline of a tagged fill
+    fn outline(fill: &Renderable) -> Vec<Point> {
+        match fill {
+            Renderable::Tagged(shape, meta) => {
+                assert_eq!(meta.get_classes(), ["fill"]);
+                match &**shape {
+                    Renderable::Polygon(points, _) => points.clone(),
+                    _ => panic!("expected polygon"),
+                }
+            }
+            _ => panic!("expected tagged fill"),
+        }
+    }
+
+    #[test]
+    fn area_fills_below_line() {
+        let blocks = [Block(4.0, 1.0), Block(4.0, 3.0)];
+        let area = Area::new(&blocks);
+        let data = data(area.renderables());
+        let points = outline(&data[0]);
+
+        assert_eq!(data.len(), 1 + 1, "fill before series");
+        assert_eq!(points.len(), 4);
+        assert_eq!(
+            points[2].y,
+            area.line.baseline_y(),
+            "closed along the baseline"
+        );
+    }
+
+    #[test]
+    fn fill_follows_interpolation() {
+        let blocks = [Block(4.0, 1.0), Block(4.0, 3.0)];
+        let area = Area::multi(vec![
+            Series::new("", &blocks).with_interpolation(Interpolation::StepAfter)
+        ]);
+        let points = outline(&data(area.renderables())[0]);
+        let stack = StackedArea::new(vec![
+            Series::new("a", &blocks),
+            Series::new("b", &blocks).with_interpolation(Interpolation::CatmullRom),
+        ]);
+
+        assert_eq!(points.len(), 3 + 3, "held value, then the jump");
+        assert_eq!(
+            (points[1].x, points[1].y),
+            (points[2].x, points[0].y),
+            "holds until the next point"
+        );
+        assert_eq!(
+            outline(&data(stack.renderables())[1]).len(),
+            1 + 16 + 2,
+            "curve over the straight layer"
+        );
+    }
+
+    #[test]
+    fn layers_accumulate() {
+        let drums = [Block(4.0, 1.0), Block(4.0, 2.0), Block(4.0, 1.0)];
+        let bass = [Block(4.0, 2.0), Block(4.0, 2.0)];
+        let stack = StackedArea::new(vec![
+            Series::new("drums", &drums),
+            Series::new("bass", &bass),
+        ]);
+        let top: Vec<f64> = stack.layers[1].iter().map(|x| x.1).collect();
+
+        assert_eq!(top, vec![3.0, 4.0, 1.0], "shorter series adds nothing");
+        assert_eq!(stack.size.h, 5.0 * 20.0 + 80.0, "sized by the total");
+        assert_eq!(data(stack.renderables()).len(), 2 + 2, "fills and series");
+    }
+
+    #[test]
+    fn normalized_columns_are_flat() {
+        let a = [Block(4.0, 1.0), Block(4.0, 6.0)];
+        let b = [Block(4.0, 3.0), Block(4.0, 2.0)];
+        let stack =
+            StackedArea::new(vec![Series::new("a", &a), Series::new("b", &b)]).with_normalization();
+
+        assert_eq!(stack.layers[0][0].1, 1.0, "a quarter");
+        assert_eq!(stack.layers[0][1].1, 3.0, "three quarters");
+        assert!(stack.layers[1].iter().all(|x| x.1 == NORMALIZED));
+    }
+}
@@ -0,0 +1,484 @@
+use super::{
+    bottom_tick, label, left_tick, scaled, ticks, Graph, Scalable, CHAR_HEIGHT, CHAR_WIDTH,
+};
+use crate::colormap::Palette;
+use crate::scale::Scale;
+use crate::{Block, Dimension, Meta, Point, Renderable, ShapeRenderer, Style};
+
+/// How several series share a category
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub enum BarLayout {
+    /// Side by side
+    Grouped,
+    /// On top of each other
+    Stacked,
+}
+
+/// Direction bars grow in
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub enum Orientation {
+    Vertical,
+    Horizontal,
+}
+
+/// Bars per named category, values of one unit spanning one base block.
+/// Categories lie on no time axis, so there is neither a viewport nor a
+/// `Plot` to place overlays by time; pass only the categories to be drawn.
+pub struct Bar {
+    size: Dimension,
+    margin: Dimension,
+    base: Block,
+    categories: Vec<String>,
+    series: Vec<Vec<f64>>,
+    width: f64,
+    gap: f64,
+    layout: BarLayout,
+    orientation: Orientation,
+    values: bool,
+    palette: Palette,
+    value_scale: Option<Scale>,
+    /// Scale positions bars grow from and reach at most
+    origin: f64,
+    peak: f64,
+}
+
+impl Bar {
+    /// One value per category, negative values drawn as zero
+    pub fn new(categories: &[&str], values: &[f64]) -> Self {
+        let base = Block(20.0, 20.0);
+        let mut bar = Self {
+            size: Dimension { w: 0.0, h: 0.0 },
+            margin: Dimension { w: 40.0, h: 40.0 },
+            width: base.0,
+            gap: base.0 / 2.0,
+            base,
+            categories: categories.iter().map(|x| x.to_string()).collect(),
+            series: vec![values.to_vec()],
+            layout: BarLayout::Grouped,
+            orientation: Orientation::Vertical,
+            values: false,
+            palette: Palette::default(),
+            value_scale: None,
+            origin: 0.0,
+            peak: 0.0,
+        };
+        bar.resize();
+        bar
+    }
+
+    /// Another value per category, colored by the next palette entry
+    pub fn with_series(mut self, values: &[f64]) -> Self {
+        self.series.push(values.to_vec());
+        self.resize();
+        self
+    }
+
+    /// Thickness of a single bar, in pixels
+    pub fn with_bar_width(mut self, width: f64) -> Self {
+        self.width = width.max(1.0);
+        self.resize();
+        self
+    }
+
+    /// Space between categories, in pixels
+    pub fn with_gap(mut self, gap: f64) -> Self {
+        self.gap = gap.max(0.0);
+        self.resize();
+        self
+    }
+
+    pub fn with_layout(mut self, layout: BarLayout) -> Self {
+        self.layout = layout;
+        self.resize();
+        self
+    }
+
+    pub fn with_orientation(mut self, orientation: Orientation) -> Self {
+        self.orientation = orientation;
+        self.resize();
+        self
+    }
+
+    /// Writes the value at the end of each bar, or of each stack
+    pub fn with_values(mut self) -> Self {
+        self.values = true;
+        self
+    }
+
+    pub fn with_palette(mut self, palette: Palette) -> Self {
+        self.palette = palette;
+        self
+    }
+
+    /// Maps values onto the value axis, labelled with ticks. One base block
+    /// spans one unit of scale position, bars growing from zero or, where the
+    /// scale is undefined at zero, the lowest whole position drawn.
+    pub fn with_value_scale(mut self, scale: Scale) -> Self {
+        self.value_scale = Some(scale);
+        self.resize();
+        self
+    }
+
+    fn value(&self, series: usize, category: usize) -> f64 {
+        self.series[series]
+            .get(category)
+            .map_or(0.0, |x| x.max(0.0))
+    }
+
+    /// Space taken by the bars of one category
+    fn group(&self) -> f64 {
+        match self.layout {
+            BarLayout::Grouped => self.width * self.series.len() as f64,
+            BarLayout::Stacked => self.width,
+        }
+    }
+
+    /// Pixels per value unit
+    fn unit(&self) -> f64 {
+        match self.orientation {
+            Orientation::Vertical => self.base.1,
+            Orientation::Horizontal => self.base.0,
+        }
+    }
+
+    /// Values bars end at, running totals when stacked
+    fn ends(&self) -> Vec<f64> {
+        (0..self.categories.len())
+            .flat_map(|category| {
+                (0..self.series.len()).scan(0.0, move |total, series| {
+                    let value = self.value(series, category);
+                    *total += value;
+                    Some(match self.layout {
+                        BarLayout::Grouped => value,
+                        BarLayout::Stacked => *total,
+                    })
+                })
+            })
+            .collect()
+    }
+
+    /// Scale position of a value, bars starting at the origin
+    fn position(&self, value: f64) -> f64 {
+        scaled(self.value_scale.as_ref(), value)
+    }
+
+    /// Distance of a value from the origin in scale positions, nothing where
+    /// the scale is undefined
+    fn length(&self, value: f64) -> f64 {
+        match self.position(value) - self.origin {
+            length if length.is_finite() => length.max(0.0),
+            _ => 0.0,
+        }
+    }
+
+    /// Scale positions and values of the value axis labels
+    fn value_ticks(&self) -> Vec<(f64, f64)> {
+        match &self.value_scale {
+            Some(scale) => ticks(
+                scale,
+                (self.origin, self.origin + self.peak.floor() + 1.0),
+                (self.peak.floor() + 1.0) * self.unit(),
+                match self.orientation {
+                    Orientation::Vertical => CHAR_HEIGHT * 2.0,
+                    Orientation::Horizontal => CHAR_WIDTH * 10.0,
+                },
+            ),
+            None => Vec::new(),
+        }
+    }
+
+    /// Margin grows to fit category labels left of horizontal bars, and value
+    /// labels left of vertical ones
+    fn resize(&mut self) {
+        let positions: Vec<f64> = self
+            .ends()
+            .into_iter()
+            .map(|x| self.position(x))
+            .filter(|x| x.is_finite())
+            .collect();
+        self.origin = match self.position(0.0) {
+            origin if origin.is_finite() => origin,
+            _ => positions
+                .iter()
+                .cloned()
+                .reduce(f64::min)
+                .map_or(0.0, f64::floor),
+        };
+        self.peak = positions
+            .iter()
+            .map(|x| x - self.origin)
+            .fold(0.0, f64::max);
+        let length = (self.peak.floor() + 1.0) * self.unit();
+        let across = self.categories.len() as f64 * (self.group() + self.gap);
+        self.margin.w = match self.orientation {
+            Orientation::Vertical => {
+                let longest = self.value_ticks().iter().map(|x| label(x.1).len()).max();
+                40.0f64.max((longest.unwrap_or(0) as f64 + 2.0) * CHAR_WIDTH)
+            }
+            Orientation::Horizontal => {
+                let longest = self.categories.iter().map(|x| x.chars().count()).max();
+                40.0f64.max(longest.unwrap_or(0) as f64 * CHAR_WIDTH + CHAR_WIDTH * 2.0)
+            }
+        };
+        let (w, h) = match self.orientation {
+            Orientation::Vertical => (across, length),
+            Orientation::Horizontal => (length, across),
+        };
+        self.size = Dimension {
+            w: w + self.margin.w * 2.0,
+            h: h + self.margin.h * 2.0,
+        };
+    }
+
+    /// Bar spanning `from..to` in values, `offset` pixels along the category axis
+    fn bar(&self, offset: f64, from: f64, to: f64, style: Style) -> Renderable {
+        let &Dimension { w: mw, h: mh } = self.margin();
+        let unit = self.unit();
+        let (from, to) = (self.length(from), self.length(to));
+        match self.orientation {
+            Orientation::Vertical => Renderable::Rect(
+                Point {
+                    x: mw + offset,
+                    y: self.size.h - mh - to * unit,
+                },
+                Dimension {
+                    w: self.width,
+                    h: (to - from) * unit,
+                },
+                style,
+            ),
+            Orientation::Horizontal => Renderable::Rect(
+                Point {
+                    x: mw + from * unit,
+                    y: mh + offset,
+                },
+                Dimension {
+                    w: (to - from) * unit,
+                    h: self.width,
+                },
+                style,
+            ),
+        }
+    }
+
+    /// Text just past the end of a bar
+    fn value_label(&self, offset: f64, value: f64) -> Renderable {
+        let &Dimension { w: mw, h: mh } = self.margin();
+        let text = label(value);
+        let unit = self.unit();
+        let value = self.length(value);
+        let at = match self.orientation {
+            Orientation::Vertical => Point {
+                x: mw + offset + (self.width - text.chars().count() as f64 * CHAR_WIDTH) / 2.0,
+                y: self.size.h - mh - value * unit - CHAR_HEIGHT * 1.5,
+            },
+            Orientation::Horizontal => Point {
+                x: mw + value * unit + CHAR_WIDTH / 2.0,
+                y: mh + offset + (self.width - CHAR_HEIGHT) / 2.0,
+            },
+        };
+        Renderable::Text(at, text, Style::color(0x909090))
+    }
+
+    fn category_label(&self, category: usize) -> Renderable {
+        let &Dimension { w: mw, h: mh } = self.margin();
+        let text = &self.categories[category];
+        let middle = (self.group() + self.gap) * (category as f64 + 0.5);
+        let at = match self.orientation {
+            Orientation::Vertical => Point {
+                x: mw + middle - text.chars().count() as f64 * CHAR_WIDTH / 2.0,
+                y: self.size.h - mh + CHAR_HEIGHT,
+            },
+            Orientation::Horizontal => Point {
+                x: mw - (text.chars().count() as f64 + 1.0) * CHAR_WIDTH,
+                y: mh + middle - CHAR_HEIGHT / 2.0,
+            },
+        };
+        Renderable::Text(at, text.clone(), Style::color(0x909090))
+    }
+
+    fn axis(&self) -> Vec<Renderable> {
+        let &Dimension { w: mw, h: mh } = self.margin();
+        let unit = self.unit();
+        self.value_ticks()
+            .into_iter()
+            .flat_map(|(position, value)| {
+                let length = (position - self.origin) * unit;
+                match self.orientation {
+                    Orientation::Vertical => left_tick(mw, self.size.h - mh - length, value),
+                    Orientation::Horizontal => bottom_tick(mw + length, self.size.h - mh, value),
+                }
+            })
+            .collect()
+    }
+}
+
+impl Scalable for Bar {
+    fn with_base(mut self, base: Block) -> Self {
+        // Bar width and gap were set in pixels, they follow the base along
+        let factor = base.0 / self.base.0;
+        self.width *= factor;
+        self.gap *= factor;
+        self.base = base;
+        self.resize();
+        self
+    }
+}
+
+impl Graph for Bar {
+    fn size(&self) -> &Dimension {
+        &self.size
+    }
+
+    fn base(&self) -> &Block {
+        &self.base
+    }
+
+    fn margin(&self) -> &Dimension {
+        &self.margin
+    }
+
+    fn draw<T>(&self, renderer: &mut T) -> Vec<u8>
+    where
+        T: ShapeRenderer,
+    {
+        for rect in self.renderables() {
+            renderer.draw(rect);
+        }
+
+        renderer.get_buffer().to_vec()
+    }
+
+    fn renderables(&self) -> Vec<Renderable> {
+        let mut renderables = self.grid();
+        let (mut bars, mut labels) = (Vec::new(), Vec::new());
+        for category in 0..self.categories.len() {
+            let start = (self.group() + self.gap) * category as f64 + self.gap / 2.0;
+            let mut stacked = 0.0;
+            for series in 0..self.series.len() {
+                let value = self.value(series, category);
+                let style = Style::from(self.palette.color(series));
+                let meta = Meta::class("bar")
+                    .with_data("category", &self.categories[category])
+                    .with_data("series", series)
+                    .with_data("value", value);
+                match self.layout {
+                    BarLayout::Grouped => {
+                        let offset = start + self.width * series as f64;
+                        bars.push(self.bar(offset, 0.0, value, style).with_meta(meta));
+                        if self.values {
+                            labels.push(self.value_label(offset, value));
+                        }
+                    }
+                    BarLayout::Stacked => {
+                        bars.push(
+                            self.bar(start, stacked, stacked + value, style)
+                                .with_meta(meta),
+                        );
+                        stacked += value;
+                    }
+                }
+            }
+            if self.values && self.layout == BarLayout::Stacked {
+                labels.push(self.value_label(start, stacked));
+            }
+            labels.push(self.category_label(category));
+        }
+        renderables.push(Renderable::Group(bars, Meta::class("data")));
+        renderables.append(&mut labels);
+        renderables.append(&mut self.axis());
+        renderables
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::graph::data;
+
+    /// Position and size of each bar
+    fn bars(bar: &Bar) -> Vec<(Point, Dimension)> {
+        data(bar.renderables())
+            .into_iter()
+            .map(|x| match x {
+                Renderable::Tagged(shape, meta) => {
+                    assert_eq!(meta.get_classes(), ["bar"]);
+                    match *shape {
+                        Renderable::Rect(pos, size, _) => (pos, size),
+                        _ => panic!("expected bar"),
+                    }
+                }
+                _ => panic!("expected tagged bar"),
+            })
+            .collect()
+    }
+
+    #[test]
+    fn grouped_bars_side_by_side() {
+        let bar = Bar::new(&["kick", "snare"], &[3.0, 1.0]).with_series(&[2.0, 4.0]);
+
+        assert_eq!(
+            bar.size.w,
+            2.0 * (40.0 + 10.0) + 80.0,
+            "two bars and a gap each"
+        );
+        assert_eq!(bar.size.h, 5.0 * 20.0 + 80.0, "tallest bar");
+        let (pos, size) = bars(&bar)[1];
+        assert_eq!(pos.x, 40.0 + 5.0 + 20.0, "second bar of the group");
+        assert_eq!(size.h, 40.0);
+        match &data(bar.renderables())[1] {
+            Renderable::Tagged(_, meta) => assert_eq!(
+                meta.get_data(),
+                [
+                    ("category".to_string(), "kick".to_string()),
+                    ("series".to_string(), "1".to_string()),
+                    ("value".to_string(), "2".to_string())
+                ]
+            ),
+            _ => panic!("expected tagged bar"),
+        }
+    }
+
+    #[test]
+    fn stacked_bars_add_up() {
+        let bar = Bar::new(&["kick", "snare"], &[3.0, 1.0])
+            .with_series(&[2.0, 4.0])
+            .with_layout(BarLayout::Stacked)
+            .with_values();
+        let labels: Vec<String> = bar
+            .renderables()
+            .into_iter()
+            .filter_map(|x| match x {
+                Renderable::Text(_, text, _) => Some(text),
+                _ => None,
+            })
+            .collect();
+
+        assert_eq!(bar.size.w, 2.0 * (20.0 + 10.0) + 80.0);
+        assert_eq!(bar.size.h, 6.0 * 20.0 + 80.0, "highest total");
+        assert_eq!(labels, vec!["5", "kick", "5", "snare"]);
+    }
+
+    #[test]
+    fn horizontal_bars_fit_labels() {
+        let bar = Bar::new(&["hi-hat closed", "ride"], &[2.0, 1.0])
+            .with_orientation(Orientation::Horizontal);
+
+        assert_eq!(bar.margin.w, 15.0 * CHAR_WIDTH);
+        assert_eq!(bar.size.h, 2.0 * 30.0 + 80.0);
+        let (pos, size) = bars(&bar)[0];
+        assert_eq!(pos.x, bar.margin.w);
+        assert_eq!(size.w, 40.0);
+    }
+
+    #[test]
+    fn log_scale_from_lowest_decade() {
+        let bar = Bar::new(&["kick", "snare"], &[10.0, 1000.0]).with_value_scale(Scale::Log10);
+
+        assert_eq!(bar.origin, 1.0, "zero is undefined");
+        assert_eq!(bar.size.h, 3.0 * 20.0 + 80.0);
+        let (pos, size) = bars(&bar)[1];
+        assert_eq!(size.h, 40.0, "two decades above ten");
+        assert_eq!(pos.y, 40.0 + 20.0);
+    }
+}
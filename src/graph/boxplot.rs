@@ -0,0 +1,321 @@
+use super::{
+    label, left_tick, quantile, scaled, sorted, ticks, Graph, Scalable, CHAR_HEIGHT, CHAR_WIDTH,
+};
+use crate::scale::Scale;
+use crate::{Block, Color, Dimension, Meta, Point, Renderable, ShapeRenderer, Style};
+
+/// Height of the plot area in base units, spanning the range of all values
+const HEIGHT: f64 = 8.0;
+
+/// Five number summary, whiskers reaching at most 1.5 IQR past the box
+#[derive(Debug, PartialEq)]
+struct Summary {
+    lower: f64,
+    q1: f64,
+    median: f64,
+    q3: f64,
+    upper: f64,
+    outliers: Vec<f64>,
+}
+
+impl Summary {
+    fn new(sorted: &[f64]) -> Option<Self> {
+        if sorted.is_empty() {
+            return None;
+        }
+        let (q1, q3) = (quantile(sorted, 0.25), quantile(sorted, 0.75));
+        let reach = (q3 - q1) * 1.5;
+        let inside = |x: &&f64| **x >= q1 - reach && **x <= q3 + reach;
+        Some(Self {
+            lower: *sorted.iter().find(inside)?,
+            q1,
+            median: quantile(sorted, 0.5),
+            q3,
+            upper: *sorted.iter().rev().find(inside)?,
+            outliers: sorted.iter().filter(|x| !inside(x)).cloned().collect(),
+        })
+    }
+}
+
+/// Distribution of each named group of raw values, side by side
+pub struct BoxPlot {
+    size: Dimension,
+    margin: Dimension,
+    base: Block,
+    groups: Vec<(String, Vec<f64>)>,
+    y_scale: Option<Scale>,
+}
+
+impl BoxPlot {
+    /// Values that are not finite are left out
+    pub fn new(groups: &[(&str, &[f64])]) -> Self {
+        let mut plot = Self {
+            size: Dimension { w: 0.0, h: 0.0 },
+            margin: Dimension { w: 40.0, h: 40.0 },
+            base: Block(20.0, 20.0),
+            groups: groups
+                .iter()
+                .map(|(name, values)| (name.to_string(), sorted(values)))
+                .collect(),
+            y_scale: None,
+        };
+        plot.resize();
+        plot
+    }
+
+    /// Maps values onto the vertical axis, labelled with ticks. Quartiles are
+    /// taken of the values themselves, those the scale is undefined at
+    /// drawn at the bottom.
+    pub fn with_y_scale(mut self, scale: Scale) -> Self {
+        self.y_scale = Some(scale);
+        self.resize();
+        self
+    }
+
+    /// Scale positions of the lowest and highest value of all groups
+    fn range(&self) -> (f64, f64) {
+        let (lo, hi) = self
+            .groups
+            .iter()
+            .flat_map(|(_, values)| values.iter())
+            .map(|&x| scaled(self.y_scale.as_ref(), x))
+            .filter(|x| x.is_finite())
+            .fold((f64::MAX, f64::MIN), |(lo, hi), x| (lo.min(x), hi.max(x)));
+        if lo > hi {
+            (0.0, 1.0)
+        } else if (hi - lo).abs() < f64::EPSILON {
+            (lo - 0.5, hi + 0.5)
+        } else {
+            (lo, hi)
+        }
+    }
+
+    /// Scale positions and values of the vertical axis labels, the ends of
+    /// the range without a scale
+    fn labels(&self) -> Vec<(f64, f64)> {
+        let (lo, hi) = self.range();
+        match &self.y_scale {
+            Some(scale) => ticks(scale, (lo, hi), HEIGHT * self.base.1, CHAR_HEIGHT * 2.0),
+            None => vec![(lo, lo), (hi, hi)],
+        }
+    }
+
+    /// Margin grows to fit the value labels, kept symmetric for the grid
+    fn resize(&mut self) {
+        let longest = self.labels().iter().map(|x| label(x.1).len()).max();
+        let longest = longest.unwrap_or(0) as f64;
+        self.margin.w = 40.0f64.max(longest * CHAR_WIDTH + CHAR_WIDTH * 2.0);
+        self.size = Dimension {
+            w: self.groups.len() as f64 * self.base.0 * 2.0 + self.margin.w * 2.0,
+            h: HEIGHT * self.base.1 + self.margin.h * 2.0,
+        };
+    }
+
+    fn y(&self, value: f64) -> f64 {
+        match scaled(self.y_scale.as_ref(), value) {
+            position if position.is_finite() => self.at(position),
+            _ => self.at(self.range().0),
+        }
+    }
+
+    /// Height of a scale position
+    fn at(&self, position: f64) -> f64 {
+        let (lo, hi) = self.range();
+        self.size.h - self.margin.h - (position - lo) / (hi - lo) * HEIGHT * self.base.1
+    }
+
+    fn group(&self, center: f64, summary: &Summary) -> Vec<Renderable> {
+        let half = self.base.0 / 2.0;
+        let whisker = Style::color(0x909090).with_border(1.0);
+        let at = |x: f64, value: f64| Point {
+            x,
+            y: self.y(value),
+        };
+        let mut group = vec![
+            Renderable::Line(at(center, summary.lower), at(center, summary.q1), whisker),
+            Renderable::Line(at(center, summary.q3), at(center, summary.upper), whisker),
+            Renderable::Line(
+                at(center - half / 2.0, summary.lower),
+                at(center + half / 2.0, summary.lower),
+                whisker,
+            ),
+            Renderable::Line(
+                at(center - half / 2.0, summary.upper),
+                at(center + half / 2.0, summary.upper),
+                whisker,
+            ),
+            Renderable::Rect(
+                at(center - half, summary.q3),
+                Dimension {
+                    w: self.base.0,
+                    h: (self.y(summary.q1) - self.y(summary.q3)).max(1.0),
+                },
+                Style::color(0x6495ED)
+                    .with_border(1.0)
+                    .with_fill(Color::from(0x6495ED).with_alpha(96)),
+            ),
+            Renderable::Line(
+                at(center - half, summary.median),
+                at(center + half, summary.median),
+                Style::color(0xFFFFFF).with_border(2.0),
+            ),
+        ];
+        for &outlier in &summary.outliers {
+            group.push(
+                Renderable::Rect(
+                    Point {
+                        x: center - 2.0,
+                        y: self.y(outlier) - 2.0,
+                    },
+                    Dimension { w: 4.0, h: 4.0 },
+                    Style::color(0xFF7F50),
+                )
+                .with_meta(Meta::class("outlier").with_data("value", outlier)),
+            );
+        }
+        group
+    }
+}
+
+impl Scalable for BoxPlot {
+    fn with_base(mut self, base: Block) -> Self {
+        self.base = base;
+        self.resize();
+        self
+    }
+}
+
+impl Graph for BoxPlot {
+    fn size(&self) -> &Dimension {
+        &self.size
+    }
+
+    fn base(&self) -> &Block {
+        &self.base
+    }
+
+    fn margin(&self) -> &Dimension {
+        &self.margin
+    }
+
+    fn draw<T>(&self, renderer: &mut T) -> Vec<u8>
+    where
+        T: ShapeRenderer,
+    {
+        for rect in self.renderables() {
+            renderer.draw(rect);
+        }
+
+        renderer.get_buffer().to_vec()
+    }
+
+    fn renderables(&self) -> Vec<Renderable> {
+        let &Dimension { w: mw, h: mh } = self.margin();
+        let text = Style::color(0x909090);
+        let mut renderables = self.grid();
+        let (mut boxes, mut labels) = (Vec::new(), Vec::new());
+        for (idx, (name, values)) in self.groups.iter().enumerate() {
+            let center = mw + (idx as f64 * 2.0 + 1.0) * self.base.0;
+            if let Some(summary) = Summary::new(values) {
+                let meta = Meta::class("box")
+                    .with_data("group", name)
+                    .with_data("lower", summary.lower)
+                    .with_data("q1", summary.q1)
+                    .with_data("median", summary.median)
+                    .with_data("q3", summary.q3)
+                    .with_data("upper", summary.upper);
+                boxes.push(Renderable::Group(self.group(center, &summary), meta));
+            }
+            labels.push(Renderable::Text(
+                Point {
+                    x: center - name.chars().count() as f64 * CHAR_WIDTH / 2.0,
+                    y: self.size.h - mh + CHAR_HEIGHT,
+                },
+                name.clone(),
+                text,
+            ));
+        }
+        renderables.push(Renderable::Group(boxes, Meta::class("data")));
+        renderables.append(&mut labels);
+        for (position, value) in self.labels() {
+            if self.y_scale.is_some() {
+                renderables.append(&mut left_tick(mw, self.at(position), value));
+                continue;
+            }
+            let text_value = label(value);
+            renderables.push(Renderable::Text(
+                Point {
+                    x: mw - (text_value.chars().count() as f64 + 1.0) * CHAR_WIDTH,
+                    y: self.at(position) - CHAR_HEIGHT / 2.0,
+                },
+                text_value,
+                text,
+            ));
+        }
+        renderables
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::graph::data;
+
+    #[test]
+    fn quartiles_and_outliers() {
+        let values = sorted(&[1.0, 2.0, 3.0, 4.0, 5.0, 6.0, 7.0, 8.0, 9.0, 40.0]);
+        let summary = Summary::new(&values).unwrap();
+
+        assert_eq!(summary.q1, 3.25);
+        assert_eq!(summary.median, 5.5);
+        assert_eq!(summary.q3, 7.75);
+        assert_eq!(
+            (summary.lower, summary.upper),
+            (1.0, 9.0),
+            "whiskers stop at data"
+        );
+        assert_eq!(summary.outliers, vec![40.0]);
+    }
+
+    #[test]
+    fn groups_share_range() {
+        let soft: &[f64] = &[20.0, 30.0, 40.0];
+        let loud: &[f64] = &[90.0, 100.0, 127.0];
+        let plot = BoxPlot::new(&[("soft", soft), ("loud", loud), ("none", &[])]);
+
+        assert_eq!(plot.range(), (20.0, 127.0));
+        assert_eq!(plot.size.w, 3.0 * 40.0 + 80.0);
+        assert_eq!(plot.y(127.0), 40.0, "top of plot area");
+        assert_eq!(plot.y(20.0), 40.0 + 160.0, "bottom of plot area");
+        assert_eq!(
+            plot.renderables().len(),
+            plot.grid().len() + 1 + 3 + 2,
+            "boxes, group and range labels"
+        );
+        match &data(plot.renderables())[1] {
+            Renderable::Group(shapes, meta) => {
+                assert_eq!(shapes.len(), 6);
+                assert_eq!(meta.get_classes(), ["box"]);
+                assert_eq!(
+                    meta.get_data()[0],
+                    ("group".to_string(), "loud".to_string())
+                );
+                assert_eq!(
+                    meta.get_data()[3],
+                    ("median".to_string(), "100".to_string())
+                );
+            }
+            _ => panic!("expected box"),
+        }
+    }
+
+    #[test]
+    fn log_scale_positions() {
+        let values: &[f64] = &[0.0, 1.0, 10.0, 100.0];
+        let plot = BoxPlot::new(&[("hz", values)]).with_y_scale(Scale::Log10);
+
+        assert_eq!(plot.range(), (0.0, 2.0), "zero is undefined");
+        assert_eq!(plot.y(10.0), 40.0 + 80.0, "middle decade");
+        assert_eq!(plot.y(0.0), 40.0 + 160.0, "drawn at the bottom");
+    }
+}
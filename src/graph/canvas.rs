@@ -0,0 +1,169 @@
+use super::{Graph, Scalable};
+use crate::{Block, Dimension, Point, Renderable, ShapeRenderer, Style};
+
+/// Attempts at converging on the canvas size, margins and labels not scaling along
+const ROUNDS: usize = 16;
+
+/// Graph scaled to fill a canvas of fixed size, centered in whatever space is left
+pub struct Canvas<G> {
+    size: Dimension,
+    graph: G,
+    /// Base cell the graph was created with
+    original: (f64, f64),
+    aspect: bool,
+    offset: (f64, f64),
+}
+
+impl<G: Scalable> Canvas<G> {
+    /// Stretches base cells independently to fill the canvas
+    pub fn new(graph: G, size: Dimension) -> Self {
+        let &Block(w, h) = graph.base();
+        Self {
+            size,
+            graph,
+            original: (w, h),
+            aspect: false,
+            offset: (0.0, 0.0),
+        }
+        .fit()
+    }
+
+    /// Scales base cell sides alike, keeping the graph's proportions
+    pub fn with_aspect_ratio(mut self) -> Self {
+        self.aspect = true;
+        self.fit()
+    }
+
+    pub fn graph(&self) -> &G {
+        &self.graph
+    }
+
+    fn fits(&self) -> bool {
+        let size = self.graph.size();
+        size.w <= self.size.w + 0.5 && size.h <= self.size.h + 0.5
+    }
+
+    fn fit(mut self) -> Self {
+        let (w, h) = self.original;
+        let mut scale = (1.0, 1.0);
+        for _ in 0..ROUNDS {
+            self.graph = self.graph.with_base(Block(w * scale.0, h * scale.1));
+            let &Dimension { w: gw, h: gh } = self.graph.size();
+            let &Dimension { w: mw, h: mh } = self.graph.margin();
+            let factor = |target: f64, size: f64, margin: f64| {
+                let content = size - margin * 2.0;
+                if content > 0.0 {
+                    (target - margin * 2.0).max(1.0) / content
+                } else {
+                    1.0
+                }
+            };
+            let (mut fx, mut fy) = (factor(self.size.w, gw, mw), factor(self.size.h, gh, mh));
+            if self.aspect {
+                fx = fx.min(fy);
+                fy = fx;
+            }
+            if (fx - 1.0).abs() < 1e-3 && (fy - 1.0).abs() < 1e-3 && self.fits() {
+                break;
+            }
+            scale = (scale.0 * fx, scale.1 * fy);
+        }
+        for _ in 0..ROUNDS {
+            if self.fits() {
+                break;
+            }
+            scale = (scale.0 * 0.9, scale.1 * 0.9);
+            self.graph = self.graph.with_base(Block(w * scale.0, h * scale.1));
+        }
+        let &Dimension { w: gw, h: gh } = self.graph.size();
+        self.offset = ((self.size.w - gw) / 2.0, (self.size.h - gh) / 2.0);
+        self
+    }
+}
+
+impl<G: Scalable> Graph for Canvas<G> {
+    fn size(&self) -> &Dimension {
+        &self.size
+    }
+
+    fn base(&self) -> &Block {
+        self.graph.base()
+    }
+
+    /// Graph will take care of this
+    fn margin(&self) -> &Dimension {
+        &Dimension { w: 0.0, h: 0.0 }
+    }
+
+    fn draw<T>(&self, renderer: &mut T) -> Vec<u8>
+    where
+        T: ShapeRenderer,
+    {
+        for rect in self.renderables() {
+            renderer.draw(rect);
+        }
+
+        renderer.get_buffer().to_vec()
+    }
+
+    fn renderables(&self) -> Vec<Renderable> {
+        let mut renderables = vec![Renderable::Rect(
+            Point { x: 0.0, y: 0.0 },
+            self.size,
+            Style::color(0x060910),
+        )];
+        let (dx, dy) = self.offset;
+        renderables.extend(
+            self.graph
+                .renderables()
+                .into_iter()
+                .map(|x| x.translate(dx, dy)),
+        );
+        renderables
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{ppm, Hits, Roll, Track};
+    use rsound_output::Buffer;
+
+    #[test]
+    fn stretches_to_canvas() {
+        let blocks: Vec<Block> = (0..2000).map(|i| Block(1.0, (i % 12) as f64)).collect();
+        let canvas = Canvas::new(Hits::new(&blocks), Dimension::new(400.0, 200.0));
+        let size = canvas.graph().size();
+
+        assert!((size.w - 400.0).abs() <= 0.5, "width {}", size.w);
+        assert!((size.h - 200.0).abs() <= 0.5, "height {}", size.h);
+        assert!(canvas.base().0 < 1.0, "cells shrink below a pixel");
+    }
+
+    #[test]
+    fn aspect_ratio_centers() {
+        let blocks = [Block(4.0, 1.0), Block(4.0, 3.0), Block(4.0, 2.0)];
+        let canvas =
+            Canvas::new(Roll::new(&blocks), Dimension::new(800.0, 800.0)).with_aspect_ratio();
+        let &Block(w, h) = canvas.base();
+
+        assert!((w - h).abs() < 1e-9, "square cells");
+        assert!(
+            (canvas.graph().size().w - 800.0).abs() <= 0.5,
+            "limited by width"
+        );
+        assert!(canvas.offset.1 > 0.0, "centered vertically");
+    }
+
+    #[test]
+    fn composite_fits_and_draws() {
+        let hits = [Block(2.0, 1.0), Block(2.0, 4.0)];
+        let notes = [Block(2.0, 60.0), Block(2.0, 64.0)];
+        let canvas = Canvas::new(Track::new(&hits, &notes), Dimension::new(300.0, 240.0));
+        let mut renderer = ppm::Renderer::new(canvas.size());
+        canvas.draw(&mut renderer);
+
+        assert!(canvas.fits());
+        assert_eq!(renderer.get_buffer().len(), 300 * 240 * 3);
+    }
+}
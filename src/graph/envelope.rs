@@ -0,0 +1,439 @@
+use super::{
+    label, left_tick, scaled, ticks, Graph, Plot, Scalable, Viewport, CHAR_HEIGHT, CHAR_WIDTH,
+};
+use crate::scale::Scale;
+use crate::{Block, Color, Dimension, Meta, Point, Renderable, ShapeRenderer, Style};
+
+/// Shape of the transition from one breakpoint to the next
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub enum Curve {
+    Linear,
+    /// Bends with the given steepness; positive values move fast early on,
+    /// negative ones late
+    Exponential(f64),
+    /// Holds the value until the next breakpoint
+    Step,
+}
+
+impl Curve {
+    /// Progress between two values at `t` within 0..=1
+    fn at(&self, t: f64) -> f64 {
+        match *self {
+            Curve::Linear => t,
+            Curve::Exponential(k) if k.abs() > f64::EPSILON => {
+                (1.0 - (-k * t).exp()) / (1.0 - (-k).exp())
+            }
+            Curve::Exponential(_) => t,
+            Curve::Step => 0.0,
+        }
+    }
+}
+
+#[derive(Debug, Copy, Clone)]
+pub struct Breakpoint {
+    pub time: f64,
+    pub value: f64,
+    /// Transition towards the following breakpoint
+    pub curve: Curve,
+}
+
+impl Breakpoint {
+    pub fn new(time: f64, value: f64) -> Self {
+        Self {
+            time,
+            value,
+            curve: Curve::Linear,
+        }
+    }
+
+    pub fn with_curve(mut self, curve: Curve) -> Self {
+        self.curve = curve;
+        self
+    }
+}
+
+pub struct Envelope {
+    size: Dimension,
+    base: Block,
+    points: Vec<Breakpoint>,
+    stages: Vec<(f64, String)>,
+    y_scale: Option<Scale>,
+    viewport: Option<Viewport>,
+    /// Scale positions of the bottom and top, taking in zero and one
+    range: (f64, f64),
+}
+
+impl Envelope {
+    /// Attack, decay and release in block duration units, sustain as level.
+    /// Sustain is held for as long as the longest of the other stages.
+    pub fn adsr(attack: f64, decay: f64, sustain: f64, release: f64) -> Self {
+        let hold = attack.max(decay).max(release);
+        let points = vec![
+            Breakpoint::new(0.0, 0.0),
+            Breakpoint::new(attack, 1.0),
+            Breakpoint::new(attack + decay, sustain),
+            Breakpoint::new(attack + decay + hold, sustain),
+            Breakpoint::new(attack + decay + hold + release, 0.0),
+        ];
+        Self::automation(points)
+            .with_stage(0.0, "A")
+            .with_stage(attack, "D")
+            .with_stage(attack + decay, "S")
+            .with_stage(attack + decay + hold, "R")
+    }
+
+    /// Arbitrary breakpoints, sorted by time
+    pub fn automation(mut points: Vec<Breakpoint>) -> Self {
+        assert!(!points.is_empty(), "there has to be a breakpoint");
+        points.sort_by(|a, b| a.time.total_cmp(&b.time));
+        let mut envelope = Self {
+            size: Dimension { w: 0.0, h: 0.0 },
+            base: Block(20.0, 20.0),
+            points,
+            stages: Vec::new(),
+            y_scale: None,
+            viewport: None,
+            range: (0.0, 1.0),
+        };
+        envelope.resize();
+        envelope
+    }
+
+    fn resize(&mut self) {
+        let values = self.points.iter().map(|p| p.value).chain([0.0, 1.0]);
+        self.range = values
+            .map(|x| scaled(self.y_scale.as_ref(), x))
+            .filter(|x| x.is_finite())
+            .fold((f64::MAX, f64::MIN), |(lo, hi), x| (lo.min(x), hi.max(x)));
+        self.range = match self.range {
+            (lo, hi) if lo < hi => (lo, hi),
+            (lo, _) if lo < f64::MAX => (lo, lo + 1.0),
+            _ => (0.0, 1.0),
+        };
+        if let Some((lo, hi)) = self.viewport.and_then(|x| x.values()) {
+            let (lo, hi) = (self.y(lo), self.y(hi));
+            if lo < hi {
+                self.range = (lo, hi);
+            }
+        }
+        let (start, end) = self.span();
+        let width = (end - start) * self.base.0;
+        self.size = Dimension {
+            w: width,
+            h: self.base.1 * 4.0,
+        };
+        let &Block(dw, dh) = self.padding();
+        let &Dimension { w: mw, h: mh } = self.margin();
+        self.size.w += (self.base.0 * dw * 2.0) + mw * 2.0;
+        self.size.h += (self.base.1 * dh * 2.0) + mh * 2.0;
+    }
+
+    /// Maps values onto the vertical axis, labelled with ticks. Values the
+    /// scale is undefined at are drawn at the bottom.
+    pub fn with_y_scale(mut self, scale: Scale) -> Self {
+        self.y_scale = Some(scale);
+        self.resize();
+        self
+    }
+
+    /// Draws only the window of time, its values fixing the height. The
+    /// envelope holds its ends on either side of the breakpoints.
+    pub fn with_viewport(mut self, viewport: Viewport) -> Self {
+        self.viewport = Some(viewport);
+        self.resize();
+        self
+    }
+
+    /// Times drawn from and to, the breakpoints unless there is a viewport
+    fn span(&self) -> (f64, f64) {
+        match self.viewport {
+            Some(viewport) => (viewport.start(), viewport.end()),
+            None => (self.points[0].time, self.points[self.points.len() - 1].time),
+        }
+    }
+
+    /// Labelled boundary drawn at the given time
+    pub fn with_stage(mut self, time: f64, name: &str) -> Self {
+        self.stages.push((time, name.to_string()));
+        self
+    }
+
+    /// Value at the given time, holding the ends outside the breakpoints
+    pub fn value(&self, time: f64) -> f64 {
+        let next = self.points.iter().position(|p| p.time > time);
+        match next {
+            Some(0) => self.points[0].value,
+            None => self.points[self.points.len() - 1].value,
+            Some(idx) => {
+                let (from, to) = (&self.points[idx - 1], &self.points[idx]);
+                let t = (time - from.time) / (to.time - from.time);
+                from.value + (to.value - from.value) * from.curve.at(t)
+            }
+        }
+    }
+
+    /// Scale position of a value, the bottom where the scale is undefined
+    fn y(&self, value: f64) -> f64 {
+        match scaled(self.y_scale.as_ref(), value) {
+            position if position.is_finite() => position,
+            _ => self.range.0,
+        }
+    }
+
+    fn point(&self, time: f64, value: f64) -> Point {
+        self.place(time, self.y(value))
+    }
+
+    /// Point at a scale position
+    fn place(&self, time: f64, position: f64) -> Point {
+        let &Block(dw, dh) = self.padding();
+        let &Dimension { w: mw, h: mh } = self.margin();
+        let (lo, hi) = self.range;
+        let height = self.base.1 * 4.0;
+        Point {
+            x: (self.base.0 * dw) + mw + (time - self.span().0) * self.base.0,
+            y: (self.base.1 * dh) + mh + height - (position - lo) / (hi - lo) * height,
+        }
+    }
+
+    /// Curve flattened to points a few pixels apart, within the times drawn
+    fn curve(&self) -> Vec<Point> {
+        let (start, end) = self.span();
+        let (first, last) = (&self.points[0], &self.points[self.points.len() - 1]);
+        let value = if start <= first.time {
+            first.value
+        } else {
+            self.value(start)
+        };
+        let mut curve = vec![self.point(start, value)];
+        if start < first.time && first.time < end {
+            curve.push(self.point(first.time, first.value));
+        }
+        for pair in self.points.windows(2) {
+            let (from, to) = (&pair[0], &pair[1]);
+            let (lo, hi) = (from.time.max(start), to.time.min(end));
+            let duration = to.time - from.time;
+            if hi < lo || (hi == lo && duration > 0.0) {
+                continue;
+            }
+            let (t0, t1) = ((lo - from.time) / duration, (hi - from.time) / duration);
+            if from.curve == Curve::Step {
+                curve.push(self.point(hi, from.value));
+            } else if duration > 0.0 {
+                let steps = (((hi - lo) * self.base.0) / 2.0).ceil().max(1.0) as usize;
+                for step in 1..steps {
+                    let t = t0 + (t1 - t0) * step as f64 / steps as f64;
+                    let value = from.value + (to.value - from.value) * from.curve.at(t);
+                    curve.push(self.point(from.time + duration * t, value));
+                }
+            }
+            if hi < to.time {
+                curve.push(self.point(hi, self.value(hi)));
+            } else {
+                curve.push(self.point(to.time, to.value));
+            }
+        }
+        if end > last.time {
+            curve.push(self.point(end, last.value));
+        }
+        curve
+    }
+
+    fn stages(&self) -> Vec<Renderable> {
+        let style = Style::color(0x909090);
+        let marker = Style::from(Color::from(0x909090).with_alpha(128));
+        let height = self.base.1 * 4.0;
+        let mut stages = Vec::new();
+        let (start, end) = self.span();
+        for (time, name) in self.stages.iter().filter(|x| x.0 >= start && x.0 <= end) {
+            let top = self.place(*time, self.range.1);
+            stages.push(
+                Renderable::Rect(top, Dimension { w: 1.0, h: height }, marker).with_meta(
+                    Meta::class("stage")
+                        .with_data("name", name)
+                        .with_data("time", time),
+                ),
+            );
+            stages.push(Renderable::Text(
+                Point {
+                    x: top.x + CHAR_WIDTH / 2.0,
+                    y: top.y - CHAR_HEIGHT * 2.0,
+                },
+                name.clone(),
+                style,
+            ));
+        }
+        stages
+    }
+}
+
+impl Scalable for Envelope {
+    fn with_base(mut self, base: Block) -> Self {
+        self.base = base;
+        self.resize();
+        self
+    }
+}
+
+impl Plot for Envelope {
+    fn position(&self, time: f64, value: f64) -> Point {
+        self.point(time, value)
+    }
+}
+
+impl Graph for Envelope {
+    fn size(&self) -> &Dimension {
+        &self.size
+    }
+
+    fn base(&self) -> &Block {
+        &self.base
+    }
+
+    fn draw<T>(&self, renderer: &mut T) -> Vec<u8>
+    where
+        T: ShapeRenderer,
+    {
+        for rect in self.renderables() {
+            renderer.draw(rect);
+        }
+
+        renderer.get_buffer().to_vec()
+    }
+
+    fn renderables(&self) -> Vec<Renderable> {
+        let curve = self.curve();
+        let baseline = self.place(self.span().0, self.y(0.0).max(self.range.0)).y;
+        let style = Style::color(0x6495ED).with_border(2.0);
+        let fill = Style::from(Color::from(0x6495ED).with_alpha(64));
+        let mut renderables = self.grid();
+
+        let mut area = curve.clone();
+        area.push(Point {
+            x: curve[curve.len() - 1].x,
+            y: baseline,
+        });
+        area.push(Point {
+            x: curve[0].x,
+            y: baseline,
+        });
+        renderables.push(Renderable::Group(
+            vec![
+                Renderable::Polygon(area, fill).with_meta(Meta::class("fill")),
+                Renderable::Group(
+                    curve
+                        .windows(2)
+                        .map(|pair| Renderable::Line(pair[0], pair[1], style))
+                        .collect(),
+                    Meta::class("curve"),
+                ),
+            ],
+            Meta::class("data"),
+        ));
+        renderables.append(&mut self.stages());
+        let top = self.place(self.span().0, self.range.1);
+        match &self.y_scale {
+            Some(scale) => {
+                for (position, value) in
+                    ticks(scale, self.range, self.base.1 * 4.0, CHAR_HEIGHT * 2.0)
+                {
+                    let y = self.place(self.span().0, position).y;
+                    renderables.append(&mut left_tick(top.x, y, value));
+                }
+            }
+            None => renderables.push(Renderable::Text(
+                Point {
+                    x: top.x - CHAR_WIDTH * 5.0,
+                    y: top.y - CHAR_HEIGHT / 2.0,
+                },
+                label(self.range.1),
+                Style::color(0x909090),
+            )),
+        }
+        renderables
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::graph::data;
+
+    #[test]
+    fn adsr_breakpoints() {
+        let envelope = Envelope::adsr(1.0, 2.0, 0.5, 3.0);
+
+        assert_eq!(envelope.value(0.0), 0.0);
+        assert_eq!(envelope.value(1.0), 1.0, "attack peak");
+        assert_eq!(envelope.value(2.0), 0.75, "halfway through decay");
+        assert_eq!(envelope.value(5.0), 0.5, "sustain");
+        assert_eq!(envelope.value(9.0), 0.0, "released");
+        assert_eq!(envelope.stages.len(), 4);
+        assert_eq!(envelope.size.w, 9.0 * 20.0 + 80.0);
+    }
+
+    #[test]
+    fn curve_interpolation() {
+        let envelope = Envelope::automation(vec![
+            Breakpoint::new(0.0, 0.0).with_curve(Curve::Step),
+            Breakpoint::new(1.0, 1.0).with_curve(Curve::Exponential(4.0)),
+            Breakpoint::new(2.0, 0.0),
+        ]);
+
+        assert_eq!(envelope.value(0.5), 0.0, "step holds");
+        assert_eq!(envelope.value(1.0), 1.0);
+        assert!(envelope.value(1.5) < 0.5, "exponential falls fast early");
+        assert_eq!(envelope.value(2.0), 0.0);
+    }
+
+    #[test]
+    fn filled_area_under_curve() {
+        let envelope = Envelope::adsr(1.0, 1.0, 0.5, 1.0);
+        let data = data(envelope.renderables());
+
+        match &data[0] {
+            Renderable::Tagged(fill, _) => match &**fill {
+                Renderable::Polygon(points, _) => {
+                    assert_eq!(points.len(), envelope.curve().len() + 2)
+                }
+                _ => panic!("expected area polygon"),
+            },
+            _ => panic!("expected tagged fill"),
+        }
+        match &data[1] {
+            Renderable::Group(lines, _) => assert_eq!(lines.len(), envelope.curve().len() - 1),
+            _ => panic!("expected curve"),
+        }
+    }
+
+    #[test]
+    fn decibel_scale() {
+        let envelope = Envelope::adsr(1.0, 1.0, 0.1, 1.0).with_y_scale(Scale::Decibel);
+
+        assert_eq!(envelope.range, (-20.0, 0.0));
+        assert_eq!(envelope.point(1.0, 1.0).y, 40.0, "full scale at the top");
+        assert_eq!(envelope.point(2.0, 0.1).y, 40.0 + 80.0);
+        assert_eq!(
+            envelope.point(0.0, 0.0).y,
+            40.0 + 80.0,
+            "silence at the bottom"
+        );
+    }
+
+    #[test]
+    fn viewport_window() {
+        let envelope = Envelope::adsr(1.0, 1.0, 0.5, 1.0).with_viewport(Viewport::new(1.5, 3.5));
+        let curve = envelope.curve();
+        let stages = envelope
+            .renderables()
+            .into_iter()
+            .filter(|x| matches!(x, Renderable::Text(..)))
+            .count();
+
+        assert_eq!(envelope.size.w, 2.0 * 20.0 + 80.0, "window width");
+        let (first, last) = (curve[0], curve[curve.len() - 1]);
+        assert_eq!((first.x, first.y), (40.0, envelope.point(1.5, 0.75).y));
+        assert_eq!((last.x, last.y), (80.0, envelope.point(3.5, 0.25).y));
+        assert_eq!(stages, 2 + 1, "sustain and release, and the top value");
+    }
+}
@@ -0,0 +1,471 @@
+use super::{label, scaled, ticks, Graph, Plot, Scalable, Viewport, CHAR_HEIGHT, CHAR_WIDTH};
+use crate::colormap::ColorScale;
+use crate::scale::Scale;
+use crate::{Block, Dimension, Meta, Point, Renderable, ShapeRenderer, Style};
+
+pub struct Heatmap {
+    size: Dimension,
+    margin: Dimension,
+    base: Block,
+    cells: Vec<Vec<f64>>,
+    colors: ColorScale,
+    value_scale: Option<Scale>,
+    rows: Vec<String>,
+    columns: Vec<String>,
+    legend: bool,
+    viewport: Option<Viewport>,
+}
+
+impl Heatmap {
+    /// Rows of cell values, `NaN` marking an empty cell
+    pub fn new(cells: Vec<Vec<f64>>) -> Self {
+        let mut heatmap = Self {
+            size: Dimension { w: 0.0, h: 0.0 },
+            margin: Dimension { w: 40.0, h: 40.0 },
+            base: Block(20.0, 20.0),
+            cells,
+            colors: ColorScale::viridis(),
+            value_scale: None,
+            rows: Vec::new(),
+            columns: Vec::new(),
+            legend: false,
+            viewport: None,
+        };
+        heatmap.resize();
+        heatmap
+    }
+
+    /// Each block spans as many cells as its duration
+    pub fn from_blocks(rows: &[&[Block]]) -> Self {
+        let mut cells: Vec<Vec<f64>> = rows
+            .iter()
+            .map(|row| {
+                row.iter()
+                    .flat_map(|block| {
+                        std::iter::repeat_n(block.1, block.0.round().max(0.0) as usize)
+                    })
+                    .collect()
+            })
+            .collect();
+        let columns = cells.iter().map(|row| row.len()).max().unwrap_or(0);
+        for row in cells.iter_mut() {
+            row.resize(columns, f64::NAN);
+        }
+        Self::new(cells)
+    }
+
+    /// Values map onto the scale domain, or the data range if it has none
+    pub fn with_colors(mut self, colors: ColorScale) -> Self {
+        self.colors = colors;
+        self.resize();
+        self
+    }
+
+    /// Maps values onto the color scale, whose domain is then given in scale
+    /// positions. Cells the scale is undefined at are left empty.
+    pub fn with_value_scale(mut self, scale: Scale) -> Self {
+        self.value_scale = Some(scale);
+        self.resize();
+        self
+    }
+
+    /// Labels drawn left of each row
+    pub fn with_row_labels(mut self, labels: &[&str]) -> Self {
+        self.rows = labels.iter().map(|x| x.to_string()).collect();
+        self.resize();
+        self
+    }
+
+    /// Labels drawn below each column
+    pub fn with_column_labels(mut self, labels: &[&str]) -> Self {
+        self.columns = labels.iter().map(|x| x.to_string()).collect();
+        self.resize();
+        self
+    }
+
+    /// Draws color bar with the value range right of the cells
+    pub fn with_legend(mut self) -> Self {
+        self.legend = true;
+        self.resize();
+        self
+    }
+
+    /// Draws only the columns within the window, one column per duration unit
+    /// as with `from_blocks`, its values ignored. Colors still fit every cell,
+    /// holding still while the window moves.
+    pub fn with_viewport(mut self, viewport: Viewport) -> Self {
+        self.viewport = Some(viewport);
+        self.resize();
+        self
+    }
+
+    fn columns(&self) -> usize {
+        self.cells.iter().map(|row| row.len()).max().unwrap_or(0)
+    }
+
+    /// First column drawn and the one after the last, all of them unless there
+    /// is a viewport
+    fn window(&self) -> (usize, usize) {
+        let columns = self.columns();
+        match self.viewport {
+            Some(viewport) => (
+                (viewport.start().floor() as usize).min(columns),
+                (viewport.end().ceil() as usize).min(columns),
+            ),
+            None => (0, columns),
+        }
+    }
+
+    /// Margin grows to fit labels and legend, kept symmetric for the grid
+    fn resize(&mut self) {
+        let longest = |labels: &[String]| {
+            labels.iter().map(|x| x.chars().count()).max().unwrap_or(0) as f64 * CHAR_WIDTH
+        };
+        let mut mw: f64 = 40.0;
+        if !self.rows.is_empty() {
+            mw = mw.max(longest(&self.rows) + CHAR_WIDTH * 2.0);
+        }
+        if self.legend {
+            let values: Vec<String> = self.ticks().iter().map(|x| label(x.1)).collect();
+            let legend = longest(&values);
+            mw = mw.max(self.base.0 + legend + CHAR_WIDTH * 3.0);
+        }
+        self.margin.w = mw;
+        let (start, end) = self.window();
+        self.size = Dimension {
+            w: (end - start) as f64 * self.base.0 + mw * 2.0,
+            h: self.cells.len() as f64 * self.base.1 + self.margin.h * 2.0,
+        };
+    }
+
+    /// Scale position of a cell value
+    fn level(&self, value: f64) -> f64 {
+        scaled(self.value_scale.as_ref(), value)
+    }
+
+    /// Color scale fitted to the finite cell positions
+    fn scale(&self) -> ColorScale {
+        let range = self
+            .cells
+            .iter()
+            .flatten()
+            .map(|&x| self.level(x))
+            .filter(|x| x.is_finite())
+            .map(|x| (x, x))
+            .reduce(|(lo, hi), (v, _)| (lo.min(v), hi.max(v)))
+            .unwrap_or((0.0, 1.0));
+        self.colors.clone().fit(range)
+    }
+
+    fn labels(&self) -> Vec<Renderable> {
+        let &Dimension { w: mw, h: mh } = self.margin();
+        let style = Style::color(0x909090);
+        let mut labels = Vec::new();
+        for (row, text) in self.rows.iter().enumerate() {
+            labels.push(Renderable::Text(
+                Point {
+                    x: mw - (text.chars().count() as f64 + 1.0) * CHAR_WIDTH,
+                    y: mh + row as f64 * self.base.1 + (self.base.1 - CHAR_HEIGHT) / 2.0,
+                },
+                text.clone(),
+                style,
+            ));
+        }
+        let (start, end) = self.window();
+        for (column, text) in self.columns.iter().enumerate().take(end).skip(start) {
+            labels.push(Renderable::Text(
+                Point {
+                    x: mw + (column - start) as f64 * self.base.0,
+                    y: self.size.h - mh + CHAR_HEIGHT,
+                },
+                text.clone(),
+                style,
+            ));
+        }
+        labels
+    }
+
+    /// Domain positions and values labelled along the legend, its ends
+    /// without a scale
+    fn ticks(&self) -> Vec<(f64, f64)> {
+        let (lo, hi) = self.scale().domain();
+        match &self.value_scale {
+            Some(scale) => ticks(
+                scale,
+                (lo, hi),
+                self.cells.len() as f64 * self.base.1,
+                CHAR_HEIGHT * 2.0,
+            ),
+            None => vec![(hi, hi), (lo, lo)],
+        }
+    }
+
+    fn legend(&self, scale: &ColorScale) -> Vec<Renderable> {
+        let &Dimension { w: mw, h: mh } = self.margin();
+        let (lo, hi) = scale.domain();
+        let left = self.size.w - mw + CHAR_WIDTH * 2.0;
+        let height = self.size.h - mh * 2.0;
+        let steps = (height / 2.0).max(1.0) as usize;
+        let step = height / steps as f64;
+        let mut legend: Vec<Renderable> = (0..steps)
+            .map(|i| {
+                Renderable::Rect(
+                    Point {
+                        x: left,
+                        y: mh + i as f64 * step,
+                    },
+                    Dimension {
+                        w: self.base.0 / 2.0,
+                        h: step.ceil(),
+                    },
+                    Style::from(scale.at(1.0 - i as f64 / (steps - 1).max(1) as f64)),
+                )
+            })
+            .collect();
+        let style = Style::color(0x909090);
+        let x = left + self.base.0 / 2.0 + CHAR_WIDTH;
+        if self.value_scale.is_none() {
+            legend.push(Renderable::Text(Point { x, y: mh }, label(hi), style));
+            legend.push(Renderable::Text(
+                Point {
+                    x,
+                    y: self.size.h - mh - CHAR_HEIGHT,
+                },
+                label(lo),
+                style,
+            ));
+            return legend;
+        }
+        for (position, value) in self.ticks() {
+            let y = mh + (hi - position) / (hi - lo) * height - CHAR_HEIGHT / 2.0;
+            legend.push(Renderable::Text(
+                Point {
+                    x,
+                    y: y.clamp(mh, self.size.h - mh - CHAR_HEIGHT),
+                },
+                label(value),
+                style,
+            ));
+        }
+        legend
+    }
+}
+
+impl Scalable for Heatmap {
+    fn with_base(mut self, base: Block) -> Self {
+        self.base = base;
+        self.resize();
+        self
+    }
+}
+
+impl Plot for Heatmap {
+    /// Middle of a row, counted from the top, one column per duration unit
+    fn position(&self, time: f64, value: f64) -> Point {
+        let &Dimension { w: mw, h: mh } = self.margin();
+        Point {
+            x: mw + (time - self.window().0 as f64) * self.base.0,
+            y: mh + (value + 0.5) * self.base.1,
+        }
+    }
+}
+
+impl Graph for Heatmap {
+    fn size(&self) -> &Dimension {
+        &self.size
+    }
+
+    fn base(&self) -> &Block {
+        &self.base
+    }
+
+    fn margin(&self) -> &Dimension {
+        &self.margin
+    }
+
+    fn draw<T>(&self, renderer: &mut T) -> Vec<u8>
+    where
+        T: ShapeRenderer,
+    {
+        for rect in self.renderables() {
+            renderer.draw(rect);
+        }
+
+        renderer.get_buffer().to_vec()
+    }
+
+    fn renderables(&self) -> Vec<Renderable> {
+        let &Dimension { w: mw, h: mh } = self.margin();
+        let scale = self.scale();
+        let (start, end) = self.window();
+        let mut renderables = self.grid();
+        let mut cells = Vec::new();
+        for (y, row) in self.cells.iter().enumerate() {
+            for (x, &raw) in row.iter().enumerate().take(end).skip(start) {
+                let value = self.level(raw);
+                if !value.is_finite() {
+                    continue;
+                }
+                let meta = Meta::class("cell")
+                    .with_data("row", y)
+                    .with_data("column", x)
+                    .with_data("value", raw);
+                cells.push(
+                    Renderable::Rect(
+                        Point {
+                            x: mw + (x - start) as f64 * self.base.0,
+                            y: mh + y as f64 * self.base.1,
+                        },
+                        Dimension {
+                            w: self.base.0,
+                            h: self.base.1,
+                        },
+                        Style::from(scale.color(value)),
+                    )
+                    .with_meta(meta),
+                );
+            }
+        }
+        renderables.push(Renderable::Group(cells, Meta::class("data")));
+        renderables.append(&mut self.labels());
+        if self.legend {
+            renderables.append(&mut self.legend(&scale));
+        }
+        renderables
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::graph::data;
+    use crate::{ppm, svg, Color};
+
+    /// Cell shapes without their metadata
+    fn cells(heatmap: &Heatmap) -> Vec<Renderable> {
+        data(heatmap.renderables())
+            .into_iter()
+            .map(|cell| match cell {
+                Renderable::Tagged(shape, _) => *shape,
+                _ => panic!("expected tagged cell"),
+            })
+            .collect()
+    }
+
+    #[test]
+    fn dimensions_from_cells() {
+        let heatmap = Heatmap::new(vec![vec![0.0, 1.0, 2.0], vec![3.0, 4.0, 5.0]]);
+
+        assert_eq!(heatmap.size.w, 3.0 * 20.0 + 80.0, "heatmap width");
+        assert_eq!(heatmap.size.h, 2.0 * 20.0 + 80.0, "heatmap height");
+    }
+
+    #[test]
+    fn cells_from_blocks() {
+        let heatmap =
+            Heatmap::from_blocks(&[&[Block(2.0, 1.0), Block(1.0, 0.0)], &[Block(1.0, 4.0)]]);
+
+        assert_eq!(heatmap.cells[0], vec![1.0, 1.0, 0.0]);
+        assert_eq!(heatmap.cells[1][0], 4.0);
+        assert!(heatmap.cells[1][1].is_nan(), "short rows are padded");
+    }
+
+    #[test]
+    fn cells_colored_by_value() {
+        let heatmap = Heatmap::new(vec![vec![0.0, f64::NAN, 10.0]])
+            .with_colors(ColorScale::new(&[0x000000, 0xFFFFFF]));
+        let cells = cells(&heatmap);
+
+        assert_eq!(cells.len(), 2, "empty cells are skipped");
+        if let Renderable::Rect(_, _, style) = &cells[1] {
+            assert_eq!(style.get_color(), &Color::from(0xFFFFFF));
+        } else {
+            panic!("expected cell rect");
+        }
+    }
+
+    #[test]
+    fn labels_widen_margin() {
+        let heatmap = Heatmap::new(vec![vec![1.0], vec![2.0]])
+            .with_row_labels(&["kick drum", "snare"])
+            .with_legend();
+
+        assert!(heatmap.margin.w > 40.0);
+        assert_eq!(heatmap.size.w, 20.0 + heatmap.margin.w * 2.0);
+    }
+
+    #[test]
+    fn renders_raster_and_vector() {
+        let heatmap = Heatmap::new(vec![vec![1.0, 2.0], vec![3.0, 4.0]])
+            .with_column_labels(&["1", "2"])
+            .with_legend();
+        let mut raster = ppm::Renderer::new(heatmap.size());
+        let buf = heatmap.draw(&mut raster);
+        assert_eq!(buf.len(), (heatmap.size.w * heatmap.size.h) as usize * 3);
+
+        let mut vector = svg::Renderer::new(heatmap.size());
+        let buf = heatmap.draw(&mut vector);
+        assert!(String::from_utf8(buf).unwrap().contains("<text"));
+    }
+
+    #[test]
+    fn cells_colored_by_scale_position() {
+        let heatmap = Heatmap::new(vec![vec![1.0, 10.0, 0.0, 100.0]])
+            .with_colors(ColorScale::new(&[0x000000, 0xFFFFFF]))
+            .with_value_scale(Scale::Log10);
+        let cells = cells(&heatmap);
+
+        assert_eq!(cells.len(), 3, "zero is undefined");
+        match (&cells[1], &cells[2]) {
+            (Renderable::Rect(_, _, middle), Renderable::Rect(_, _, top)) => {
+                assert_eq!(
+                    middle.get_color(),
+                    &ColorScale::new(&[0x000000, 0xFFFFFF]).at(0.5)
+                );
+                assert_eq!(top.get_color(), &Color::from(0xFFFFFF));
+            }
+            _ => panic!("expected cell rects"),
+        }
+    }
+
+    #[test]
+    fn viewport_columns() {
+        let heatmap = Heatmap::new(vec![vec![0.0, 1.0, 2.0, 3.0], vec![4.0, 5.0, 6.0, 7.0]])
+            .with_column_labels(&["a", "b", "c", "d"])
+            .with_viewport(Viewport::new(1.5, 3.0));
+        let renderables = heatmap.renderables();
+        let idx = heatmap.grid().len();
+
+        assert_eq!(heatmap.window(), (1, 3));
+        assert_eq!(heatmap.size.w, 2.0 * 20.0 + 80.0);
+        assert_eq!(
+            renderables.len(),
+            idx + 1 + 2,
+            "cells and labels of two columns"
+        );
+        match &data(heatmap.renderables())[0] {
+            Renderable::Tagged(cell, meta) => {
+                match **cell {
+                    Renderable::Rect(pos, _, _) => {
+                        assert_eq!(pos.x, 40.0, "first column in the window")
+                    }
+                    _ => panic!("expected cell"),
+                }
+                assert_eq!(meta.get_data()[1], ("column".to_string(), "1".to_string()));
+            }
+            _ => panic!("expected tagged cell"),
+        }
+        match &renderables[idx + 2] {
+            Renderable::Text(_, text, _) => assert_eq!(text, "c"),
+            _ => panic!("expected label"),
+        }
+    }
+
+    #[test]
+    fn plotted_by_column_and_row() {
+        let heatmap = Heatmap::new(vec![vec![0.0; 4]; 2]).with_viewport(Viewport::new(1.0, 4.0));
+        let at = heatmap.position(2.0, 1.0);
+
+        assert_eq!((at.x, at.y), (40.0 + 20.0, 40.0 + 30.0));
+    }
+}
@@ -0,0 +1,413 @@
+use super::{
+    bottom_tick, label, quantile, sorted, ticks, Graph, Scalable, CHAR_HEIGHT, CHAR_WIDTH,
+};
+use crate::scale::Scale;
+use crate::{Block, Dimension, Meta, Point, Renderable, ShapeRenderer, Style};
+
+/// Height of the plot area in base units, the tallest bin reaching the top
+const HEIGHT: f64 = 8.0;
+const MAX_BINS: usize = 512;
+
+/// How raw values are divided into bins
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub enum Binning {
+    Count(usize),
+    Width(f64),
+    /// Twice the interquartile range over the cube root of the value count
+    FreedmanDiaconis,
+}
+
+/// Counts of raw values per bin, each bin one base block wide
+pub struct Histogram {
+    size: Dimension,
+    base: Block,
+    raw: Vec<f64>,
+    /// Scale positions of the finite values, in ascending order
+    values: Vec<f64>,
+    x_scale: Option<Scale>,
+    binning: Binning,
+    cumulative: bool,
+    density: bool,
+}
+
+impl Histogram {
+    /// Values that are not finite are left out
+    pub fn new(values: &[f64]) -> Self {
+        let mut histogram = Self {
+            size: Dimension { w: 0.0, h: 0.0 },
+            base: Block(20.0, 20.0),
+            raw: values.to_vec(),
+            values: sorted(values),
+            x_scale: None,
+            binning: Binning::FreedmanDiaconis,
+            cumulative: false,
+            density: false,
+        };
+        histogram.resize();
+        histogram
+    }
+
+    pub fn with_binning(mut self, binning: Binning) -> Self {
+        self.binning = binning;
+        self.resize();
+        self
+    }
+
+    /// Bins values by their scale position, equal widths on the scale, with
+    /// the bin edges labelled by value. Values the scale is undefined at are
+    /// left out.
+    pub fn with_x_scale(mut self, scale: Scale) -> Self {
+        let positions: Vec<f64> = self.raw.iter().map(|&x| scale.forward(x)).collect();
+        self.values = sorted(&positions);
+        self.x_scale = Some(scale);
+        self.resize();
+        self
+    }
+
+    /// Draws the running share of values, reaching the top at 100%
+    pub fn with_cumulative(mut self) -> Self {
+        self.cumulative = true;
+        self
+    }
+
+    /// Draws a gaussian kernel density estimate over the bins
+    pub fn with_density(mut self) -> Self {
+        self.density = true;
+        self
+    }
+
+    fn resize(&mut self) {
+        let (_, _, bins) = self.bins();
+        self.size = Dimension {
+            w: bins as f64 * self.base.0,
+            h: HEIGHT * self.base.1,
+        };
+        let &Block(dw, dh) = self.padding();
+        let &Dimension { w: mw, h: mh } = self.margin();
+        self.size.w += (self.base.0 * dw * 2.0) + mw * 2.0;
+        self.size.h += (self.base.1 * dh * 2.0) + mh * 2.0;
+    }
+
+    /// Start of the first bin, bin width and number of bins
+    fn bins(&self) -> (f64, f64, usize) {
+        let (lo, hi) = match (self.values.first(), self.values.last()) {
+            (Some(&lo), Some(&hi)) => (lo, hi),
+            _ => return (0.0, 1.0, 0),
+        };
+        let span = hi - lo;
+        if span < f64::EPSILON {
+            return (lo - 0.5, 1.0, 1);
+        }
+        let n = self.values.len() as f64;
+        let width = match self.binning {
+            Binning::Count(count) => {
+                let count = count.clamp(1, MAX_BINS);
+                return (lo, span / count as f64, count);
+            }
+            Binning::Width(width) if width > 0.0 => width,
+            Binning::Width(_) => span,
+            Binning::FreedmanDiaconis => {
+                let iqr = quantile(&self.values, 0.75) - quantile(&self.values, 0.25);
+                if iqr > 0.0 {
+                    2.0 * iqr / n.cbrt()
+                } else {
+                    span / (n.log2().ceil() + 1.0)
+                }
+            }
+        };
+        let count = ((span / width).ceil() as usize).max(1);
+        if count > MAX_BINS {
+            (lo, span / MAX_BINS as f64, MAX_BINS)
+        } else {
+            (lo, width, count)
+        }
+    }
+
+    /// Values per bin, the last bin including its upper edge
+    fn counts(&self) -> Vec<usize> {
+        let (start, width, bins) = self.bins();
+        let mut counts = vec![0; bins];
+        for value in &self.values {
+            let bin = ((value - start) / width).floor().max(0.0) as usize;
+            counts[bin.min(bins - 1)] += 1;
+        }
+        counts
+    }
+
+    /// Kernel density estimate at `x`
+    fn density_at(&self, x: f64, bandwidth: f64) -> f64 {
+        let n = self.values.len() as f64;
+        self.values
+            .iter()
+            .map(|v| (-0.5 * ((x - v) / bandwidth).powi(2)).exp())
+            .sum::<f64>()
+            / (n * bandwidth * (2.0 * std::f64::consts::PI).sqrt())
+    }
+
+    /// Silverman's rule of thumb
+    fn bandwidth(&self) -> f64 {
+        let n = self.values.len() as f64;
+        let mean = self.values.iter().sum::<f64>() / n;
+        let deviation = (self.values.iter().map(|v| (v - mean).powi(2)).sum::<f64>() / n).sqrt();
+        let iqr = quantile(&self.values, 0.75) - quantile(&self.values, 0.25);
+        let spread = if iqr > 0.0 {
+            deviation.min(iqr / 1.34)
+        } else {
+            deviation
+        };
+        0.9 * spread * n.powf(-0.2)
+    }
+}
+
+impl Scalable for Histogram {
+    fn with_base(mut self, base: Block) -> Self {
+        self.base = base;
+        self.resize();
+        self
+    }
+}
+
+impl Graph for Histogram {
+    fn size(&self) -> &Dimension {
+        &self.size
+    }
+
+    fn base(&self) -> &Block {
+        &self.base
+    }
+
+    fn draw<T>(&self, renderer: &mut T) -> Vec<u8>
+    where
+        T: ShapeRenderer,
+    {
+        for rect in self.renderables() {
+            renderer.draw(rect);
+        }
+
+        renderer.get_buffer().to_vec()
+    }
+
+    fn renderables(&self) -> Vec<Renderable> {
+        let &Block(dw, dh) = self.padding();
+        let &Dimension { w: mw, h: mh } = self.margin();
+        let left = (self.base.0 * dw) + mw;
+        let height = HEIGHT * self.base.1;
+        let bottom = (self.base.1 * dh) + mh + height;
+        let (start, width, bins) = self.bins();
+        let counts = self.counts();
+        let peak = counts.iter().cloned().max().unwrap_or(0).max(1) as f64;
+        let text = Style::color(0x909090);
+        let edge = |position: f64| match &self.x_scale {
+            Some(scale) => scale.inverse(position),
+            None => position,
+        };
+        let mut renderables = self.grid();
+        let mut data = Vec::new();
+        for (bin, &count) in counts.iter().enumerate() {
+            let h = count as f64 / peak * height;
+            let meta = Meta::class("bin")
+                .with_data("from", edge(start + bin as f64 * width))
+                .with_data("to", edge(start + (bin + 1) as f64 * width))
+                .with_data("value", count);
+            data.push(
+                Renderable::Rect(
+                    Point {
+                        x: left + bin as f64 * self.base.0,
+                        y: bottom - h,
+                    },
+                    Dimension {
+                        w: (self.base.0 - 1.0).max(self.base.0 / 2.0),
+                        h,
+                    },
+                    Style::color(0x6495ED),
+                )
+                .with_meta(meta),
+            );
+        }
+        let bandwidth = self.bandwidth();
+        if self.density && bandwidth > 0.0 {
+            let style = Style::color(0xBADA55).with_border(2.0);
+            let steps = bins * 4;
+            let points: Vec<Point> = (0..=steps)
+                .map(|step| {
+                    let t = step as f64 / steps as f64;
+                    let x = start + t * width * bins as f64;
+                    let expected = self.density_at(x, bandwidth) * self.values.len() as f64 * width;
+                    Point {
+                        x: left + t * bins as f64 * self.base.0,
+                        y: bottom - expected / peak * height,
+                    }
+                })
+                .collect();
+            data.push(Renderable::Group(
+                points
+                    .windows(2)
+                    .map(|pair| Renderable::Line(pair[0], pair[1], style))
+                    .collect(),
+                Meta::class("density"),
+            ));
+        }
+        let mut labels = Vec::new();
+        if self.cumulative && bins > 0 {
+            let style = Style::color(0xFF7F50).with_border(2.0);
+            let mut total = 0;
+            let mut previous = Point { x: left, y: bottom };
+            let mut lines = Vec::new();
+            for (bin, &count) in counts.iter().enumerate() {
+                total += count;
+                let next = Point {
+                    x: left + (bin + 1) as f64 * self.base.0,
+                    y: bottom - total as f64 / self.values.len() as f64 * height,
+                };
+                lines.push(Renderable::Line(previous, next, style));
+                previous = next;
+            }
+            data.push(Renderable::Group(lines, Meta::class("cumulative")));
+            labels.push(Renderable::Text(
+                Point {
+                    x: previous.x + CHAR_WIDTH / 2.0,
+                    y: bottom - height - CHAR_HEIGHT / 2.0,
+                },
+                "100%".to_string(),
+                text,
+            ));
+        }
+        renderables.push(Renderable::Group(data, Meta::class("data")));
+        renderables.append(&mut labels);
+        if let Some(scale) = &self.x_scale {
+            let span = (start, start + width * bins as f64);
+            for (position, value) in
+                ticks(scale, span, bins as f64 * self.base.0, CHAR_WIDTH * 10.0)
+            {
+                let x = left + (position - start) / width * self.base.0;
+                renderables.append(&mut bottom_tick(x, bottom, value));
+            }
+        } else {
+            let end = label(start + width * bins as f64);
+            let right = left + bins as f64 * self.base.0;
+            renderables.push(Renderable::Text(
+                Point {
+                    x: left,
+                    y: bottom + CHAR_HEIGHT,
+                },
+                label(start),
+                text,
+            ));
+            renderables.push(Renderable::Text(
+                Point {
+                    x: right - end.chars().count() as f64 * CHAR_WIDTH,
+                    y: bottom + CHAR_HEIGHT,
+                },
+                end,
+                text,
+            ));
+        }
+        renderables.push(Renderable::Text(
+            Point {
+                x: left - CHAR_WIDTH * 5.0,
+                y: bottom - height - CHAR_HEIGHT / 2.0,
+            },
+            label(peak),
+            text,
+        ));
+        renderables
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::graph::data;
+
+    #[test]
+    fn fixed_count_and_width() {
+        let values = [1.0, 2.0, 2.5, 3.0, 9.0];
+        let count = Histogram::new(&values).with_binning(Binning::Count(4));
+        let width = Histogram::new(&values).with_binning(Binning::Width(3.0));
+
+        assert_eq!(
+            count.counts(),
+            vec![3, 1, 0, 1],
+            "last bin includes maximum"
+        );
+        assert_eq!(count.size.w, 4.0 * 20.0 + 80.0);
+        assert_eq!(width.counts(), vec![4, 0, 1]);
+        assert_eq!(
+            Histogram::new(&values)
+                .with_binning(Binning::Count(usize::MAX))
+                .bins()
+                .2,
+            MAX_BINS,
+            "capped like automatic binning"
+        );
+    }
+
+    #[test]
+    fn freedman_diaconis_width() {
+        let values: Vec<f64> = (1..=8).map(|x| x as f64).collect();
+        let histogram = Histogram::new(&values);
+
+        assert_eq!(histogram.bins(), (1.0, 3.5, 2), "twice 3.5 over two");
+        assert_eq!(histogram.counts(), vec![4, 4]);
+    }
+
+    #[test]
+    fn cumulative_reaches_top() {
+        let values = [1.0, 1.0, 2.0, 3.0, f64::NAN];
+        let histogram = Histogram::new(&values)
+            .with_binning(Binning::Count(3))
+            .with_cumulative();
+        let last = data(histogram.renderables())
+            .into_iter()
+            .find_map(|x| match x {
+                Renderable::Group(lines, meta) if meta.get_classes() == ["cumulative"] => {
+                    match lines.last() {
+                        Some(&Renderable::Line(_, end, _)) => Some(end),
+                        _ => None,
+                    }
+                }
+                _ => None,
+            });
+
+        assert_eq!(histogram.values.len(), 4, "not a number is left out");
+        match last {
+            Some(end) => assert_eq!(end.y, 40.0),
+            None => panic!("expected cumulative curve"),
+        }
+    }
+
+    #[test]
+    fn density_matches_bins() {
+        let values: Vec<f64> = (0..200).map(|x| (x % 20) as f64).collect();
+        let histogram = Histogram::new(&values);
+        let bandwidth = histogram.bandwidth();
+        let area: f64 = (-200..400)
+            .map(|x| histogram.density_at(x as f64 / 10.0, bandwidth) / 10.0)
+            .sum();
+
+        assert!((area - 1.0).abs() < 0.01, "integrates to one, got {}", area);
+    }
+
+    #[test]
+    fn log_bins() {
+        let values = [0.0, 1.0, 10.0, 100.0, 1000.0];
+        let histogram = Histogram::new(&values)
+            .with_binning(Binning::Count(3))
+            .with_x_scale(Scale::Log10);
+
+        assert_eq!(histogram.values.len(), 4, "zero is left out");
+        assert_eq!(histogram.bins(), (0.0, 1.0, 3));
+        assert_eq!(histogram.counts(), vec![1, 1, 2]);
+        match &data(histogram.renderables())[1] {
+            Renderable::Tagged(_, meta) => assert_eq!(
+                meta.get_data(),
+                [
+                    ("from".to_string(), "10".to_string()),
+                    ("to".to_string(), "100".to_string()),
+                    ("value".to_string(), "1".to_string())
+                ]
+            ),
+            _ => panic!("expected tagged bin"),
+        }
+    }
+}
@@ -1,38 +1,150 @@
-use super::Graph;
-use crate::{Block, Dimension, Point, Renderable, ShapeRenderer, Style};
+use super::{
+    extent, left_tick, scaled, scaled_range, ticks, value_range, Graph, Plot, Scalable, Viewport,
+    CHAR_HEIGHT,
+};
+use crate::colormap::ColorScale;
+use crate::scale::Scale;
+use crate::{Block, Dimension, Meta, Point, Renderable, ShapeRenderer, Style};
 
 pub struct Hits<'a> {
     size: Dimension,
     base: Block,
     blocks: &'a [Block],
+    colors: Option<ColorScale>,
+    baseline: f64,
+    y_scale: Option<Scale>,
+    /// Scale position of the baseline
+    origin: f64,
+    /// Base units between the top and the baseline, and below it
+    above: f64,
+    below: f64,
+    viewport: Option<Viewport>,
+    /// Blocks clipped to the viewport
+    visible: Option<Vec<Block>>,
 }
 
 impl<'a> Hits<'a> {
     pub fn new(blocks: &'a [Block]) -> Self {
-        let base = Block(20.0, 20.0);
-        let width = blocks
-            .iter()
-            .fold(0.0, |total, block| total + block.0 * base.0);
-        let maximum = blocks
-            .iter()
-            .map(|x| x.1 * base.1)
-            .reduce(f64::max)
-            .expect("there has to be maximum");
-        let height = ((maximum / base.1).floor() + 1.0) * base.1;
-        let mut roll = Self {
-            size: Dimension {
-                w: width,
-                h: height,
-            },
-            base,
+        assert!(!blocks.is_empty(), "there has to be maximum");
+        let mut hits = Self {
+            size: Dimension { w: 0.0, h: 0.0 },
+            base: Block(20.0, 20.0),
             blocks,
+            colors: None,
+            baseline: 0.0,
+            y_scale: None,
+            origin: 0.0,
+            above: 0.0,
+            below: 0.0,
+            viewport: None,
+            visible: None,
         };
-        let &Block(dw, dh) = roll.padding();
-        let &Dimension { w: mw, h: mh } = roll.margin();
-        roll.size.w += (roll.base.0 * dw * 2.0) + mw * 2.0;
-        roll.size.h += (roll.base.1 * dh * 2.0) + mh * 2.0;
+        hits.resize();
+        hits
+    }
+
+    /// Value bars grow from, up or down
+    pub fn with_baseline(mut self, baseline: f64) -> Self {
+        self.baseline = baseline;
+        self.resize();
+        self
+    }
+
+    /// Maps values onto the vertical axis, labelled with ticks.
+    /// One base block spans one unit of scale position.
+    pub fn with_y_scale(mut self, scale: Scale) -> Self {
+        self.y_scale = Some(scale);
+        self.resize();
+        self
+    }
+
+    /// Fills bars by intensity, fitted to the intensity range unless scale has a domain
+    pub fn with_colors(mut self, colors: ColorScale) -> Self {
+        self.colors = Some(colors);
+        self
+    }
+
+    /// Draws only the hits within the window, its values fixing the height.
+    /// A window past the last block leaves the plot empty.
+    pub fn with_viewport(mut self, viewport: Viewport) -> Self {
+        let (_, visible) = viewport.clip(self.blocks);
+        self.viewport = Some(viewport);
+        self.visible = Some(visible);
+        self.resize();
+        self
+    }
+
+    /// Blocks drawn, all of them unless there is a viewport
+    fn blocks(&self) -> &[Block] {
+        self.visible.as_deref().unwrap_or(self.blocks)
+    }
+
+    fn resize(&mut self) {
+        let width = self
+            .blocks()
+            .iter()
+            .fold(0.0, |total, block| total + block.0 * self.base.0);
+        let range = match self.viewport.and_then(|x| x.values()) {
+            Some((lo, hi)) => (self.y(lo), self.y(hi)),
+            None => scaled_range(self.blocks(), self.y_scale.as_ref()),
+        };
+        self.origin = match self.y(self.baseline) {
+            origin if origin.is_finite() => origin,
+            _ => range.0.floor(),
+        };
+        let (above, below) = extent(range, self.origin);
+        (self.above, self.below) = (above, below);
+        self.size = Dimension {
+            w: width,
+            h: (above + below) * self.base.1,
+        };
+        let &Block(dw, dh) = self.padding();
+        let &Dimension { w: mw, h: mh } = self.margin();
+        self.size.w += (self.base.0 * dw * 2.0) + mw * 2.0;
+        self.size.h += (self.base.1 * dh * 2.0) + mh * 2.0;
+    }
+
+    /// Scale position of a value
+    fn y(&self, value: f64) -> f64 {
+        scaled(self.y_scale.as_ref(), value)
+    }
+
+    /// Ticks and labels of the vertical axis, if it is scaled
+    fn axis(&self, left: f64, zero: f64) -> Vec<Renderable> {
+        let scale = match &self.y_scale {
+            Some(scale) => scale,
+            None => return Vec::new(),
+        };
+        ticks(
+            scale,
+            (self.origin - self.below, self.origin + self.above),
+            (self.above + self.below) * self.base.1,
+            CHAR_HEIGHT * 2.0,
+        )
+        .into_iter()
+        .flat_map(|(position, value)| {
+            left_tick(left, zero - (position - self.origin) * self.base.1, value)
+        })
+        .collect()
+    }
+}
+
+impl<'a> Scalable for Hits<'a> {
+    fn with_base(mut self, base: Block) -> Self {
+        self.base = base;
+        self.resize();
+        self
+    }
+}
 
-        roll
+impl<'a> Plot for Hits<'a> {
+    fn position(&self, time: f64, value: f64) -> Point {
+        let (area, _) = self.area();
+        let start = self.viewport.map_or(0.0, |x| x.start());
+        Point {
+            x: area.x + (time - start) * self.base.0,
+            y: area.y + (self.above - (self.y(value) - self.origin)) * self.base.1,
+        }
     }
 }
 
@@ -57,51 +169,163 @@ impl<'a> Graph for Hits<'a> {
     }
 
     fn renderables(&self) -> Vec<Renderable> {
-        let &Dimension {
-            h: height,
-            w: _width,
-        } = self.size();
+        let &Dimension { w: width, .. } = self.size();
         let &Block(dw, dh) = self.padding();
         let &Dimension { w: mw, h: mh } = self.margin();
         let mut prev = Point {
             x: (self.base.0 * dw) + mw,
             y: (self.base.1 * dh) + mh,
         };
+        let zero = prev.y + self.above * self.base.1;
         let style = Style::color(0xBADA55)
             .with_border(2.0)
             .with_background(0x33EF33);
+        let scale = self
+            .colors
+            .clone()
+            .map(|colors| colors.fit(value_range(self.blocks())));
         let mut renderables = self.grid();
-        renderables.append(
-            &mut self
-                .blocks
+        if zero < self.size.h - prev.y {
+            renderables.push(Renderable::Rect(
+                Point { x: prev.x, y: zero },
+                Dimension {
+                    w: width - prev.x * 2.0,
+                    h: 1.0,
+                },
+                Style::color(0x909090),
+            ));
+        }
+        renderables.push(Renderable::Group(
+            self.blocks()
                 .iter()
                 .filter_map(|block| {
-                    if block.1 == 0.0 {
+                    if block.1 == self.baseline {
+                        prev.x += block.0 * self.base.0;
+                        return None;
+                    }
+                    let delta_y = (self.y(block.1) - self.origin).clamp(-self.below, self.above)
+                        * self.base.1;
+                    if delta_y.is_nan() {
                         prev.x += block.0 * self.base.0;
                         return None;
                     }
-                    let mut delta_y = block.1 * self.base.1;
-                    delta_y = (height - prev.y * 2.0) - delta_y;
                     let rect = Renderable::Rect(
                         Point {
                             x: prev.x,
-                            y: delta_y + prev.y,
+                            y: zero - delta_y.max(0.0),
                         },
                         Dimension {
                             w: self.base.0,
-                            h: block.1 * self.base.1,
+                            h: delta_y.abs(),
+                        },
+                        match &scale {
+                            Some(scale) => style.with_fill(scale.color(block.1)),
+                            None => style,
                         },
-                        style,
                     );
 
+                    let meta = Meta::class("hit hits")
+                        .with_title(&format!("duration {}, intensity {}", block.0, block.1))
+                        .with_data("duration", block.0)
+                        .with_data("intensity", block.1);
+
                     prev.x += block.0 * self.base.0;
-                    Some(rect)
+                    Some(rect.with_meta(meta))
                 })
-                .collect::<Vec<Renderable>>(),
-        );
+                .collect(),
+            Meta::class("data"),
+        ));
+        renderables.append(&mut self.axis((self.base.0 * dw) + mw, zero));
+        if let Some(viewport) = &self.viewport {
+            let (left, top) = ((self.base.0 * dw) + mw, (self.base.1 * dh) + mh);
+            renderables.append(&mut viewport.labels(self.blocks(), left, width - left, top));
+        }
         renderables
     }
 }
 
 #[cfg(test)]
-mod tests {}
+mod tests {
+    use super::*;
+
+    #[test]
+    fn negative_values_below_baseline() {
+        let blocks = [Block(1.0, 2.0), Block(1.0, -0.5), Block(1.0, 0.0)];
+        let hits = Hits::new(&blocks);
+        let renderables = hits.renderables();
+        let idx = hits.grid().len();
+
+        assert_eq!(hits.size.h, 3.0 * 2.0 * 20.0 + 80.0, "zero in the middle");
+        assert_eq!(renderables.len(), idx + 1 + 1, "baseline and bars");
+        let bars = match &renderables[idx + 1] {
+            Renderable::Group(bars, meta) => {
+                assert_eq!(meta.get_classes(), ["data"]);
+                bars
+            }
+            _ => panic!("expected group of bars"),
+        };
+        assert_eq!(bars.len(), 2);
+        match &bars[1] {
+            Renderable::Tagged(shape, meta) => {
+                assert_eq!(
+                    meta.get_data(),
+                    [
+                        ("duration".to_string(), "1".to_string()),
+                        ("intensity".to_string(), "-0.5".to_string())
+                    ]
+                );
+                match **shape {
+                    Renderable::Rect(pos, size, _) => {
+                        assert_eq!(pos.y, 40.0 + 60.0, "hangs from zero");
+                        assert_eq!(size.h, 10.0);
+                    }
+                    _ => panic!("expected bar"),
+                }
+            }
+            _ => panic!("expected tagged bar"),
+        }
+    }
+
+    #[test]
+    fn custom_baseline() {
+        let blocks = [Block(1.0, 64.0), Block(1.0, 66.0), Block(1.0, 63.0)];
+        let hits = Hits::new(&blocks).with_baseline(64.0);
+
+        assert_eq!(hits.size.h, 2.0 * 3.0 * 20.0 + 80.0);
+        assert_eq!(hits.renderables().len(), hits.grid().len() + 1 + 1);
+    }
+
+    #[test]
+    fn viewport_past_the_end() {
+        let blocks = [Block(1.0, 2.0), Block(1.0, 3.0)];
+        let hits = Hits::new(&blocks).with_viewport(Viewport::new(4.0, 8.0));
+
+        assert_eq!(hits.size.w, 80.0, "nothing to draw");
+        match &hits.renderables()[hits.grid().len()] {
+            Renderable::Group(bars, _) => assert!(bars.is_empty()),
+            _ => panic!("expected group of bars"),
+        }
+    }
+
+    #[test]
+    fn log_scale_in_decades() {
+        let blocks = [Block(1.0, 1.0), Block(1.0, 10.0), Block(1.0, 100.0)];
+        let hits = Hits::new(&blocks).with_y_scale(Scale::Log10);
+        let renderables = hits.renderables();
+        let labels: Vec<&str> = renderables
+            .iter()
+            .filter_map(|x| match x {
+                Renderable::Text(_, text, _) => Some(text.as_str()),
+                _ => None,
+            })
+            .collect();
+
+        assert_eq!(
+            hits.size.h,
+            3.0 * 20.0 + 80.0,
+            "undefined baseline at the bottom"
+        );
+        assert_eq!(hits.position(2.0, 100.0).y, 40.0 + 20.0);
+        assert_eq!(labels, vec!["1", "10", "100", "1000"]);
+    }
+}
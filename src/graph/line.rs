@@ -1,36 +1,707 @@
-use super::Graph;
-use crate::{Block, Dimension, Point, Renderable, ShapeRenderer, Style};
+use super::{
+    bottom_tick, extent, label, left_tick, scaled, ticks, value_range, Graph, Plot, Scalable,
+    Viewport, CHAR_HEIGHT, CHAR_WIDTH,
+};
+use crate::colormap::{ColorScale, Palette};
+use crate::scale::Scale;
+use crate::{
+    flatten, Block, Color, Dimension, Meta, PathSegment, Point, Renderable, ShapeRenderer, Style,
+};
+
+/// Shape drawn at each data point
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub enum Marker {
+    None,
+    Square,
+    Diamond,
+    Cross,
+}
+
+/// How consecutive points are joined
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub enum Interpolation {
+    Linear,
+    /// Jumps to the next value at the start of the segment
+    StepBefore,
+    /// Holds the value until the next point
+    StepAfter,
+    /// Jumps halfway between points
+    StepMiddle,
+    /// Cubic that never overshoots the data, Fritsch-Carlson
+    Monotone,
+    CatmullRom,
+    /// Catmull-Rom with tension, 0 being Catmull-Rom and 1 straight lines
+    Cardinal(f64),
+}
+
+impl Interpolation {
+    /// Path pieces joining each point to the next, the start point excluded
+    fn segments(&self, points: &[Point]) -> Vec<Vec<PathSegment>> {
+        let at = |x: f64, y: f64| Point { x, y };
+        let pairs = points.windows(2);
+        match *self {
+            Interpolation::Linear => pairs.map(|w| vec![PathSegment::LineTo(w[1])]).collect(),
+            Interpolation::StepBefore => pairs
+                .map(|w| {
+                    vec![
+                        PathSegment::LineTo(at(w[0].x, w[1].y)),
+                        PathSegment::LineTo(w[1]),
+                    ]
+                })
+                .collect(),
+            Interpolation::StepAfter => pairs
+                .map(|w| {
+                    vec![
+                        PathSegment::LineTo(at(w[1].x, w[0].y)),
+                        PathSegment::LineTo(w[1]),
+                    ]
+                })
+                .collect(),
+            Interpolation::StepMiddle => pairs
+                .map(|w| {
+                    let middle = (w[0].x + w[1].x) / 2.0;
+                    vec![
+                        PathSegment::LineTo(at(middle, w[0].y)),
+                        PathSegment::LineTo(at(middle, w[1].y)),
+                        PathSegment::LineTo(w[1]),
+                    ]
+                })
+                .collect(),
+            Interpolation::Monotone => {
+                let slopes = monotone_slopes(points);
+                pairs
+                    .enumerate()
+                    .map(|(i, w)| {
+                        let third = (w[1].x - w[0].x) / 3.0;
+                        vec![PathSegment::CubicTo(
+                            at(w[0].x + third, w[0].y + slopes[i] * third),
+                            at(w[1].x - third, w[1].y - slopes[i + 1] * third),
+                            w[1],
+                        )]
+                    })
+                    .collect()
+            }
+            Interpolation::CatmullRom => Interpolation::Cardinal(0.0).segments(points),
+            Interpolation::Cardinal(tension) => {
+                let last = points.len().saturating_sub(1);
+                let tangent = |i: usize| {
+                    let (prev, next) = (points[i.saturating_sub(1)], points[(i + 1).min(last)]);
+                    let k = (1.0 - tension) / 2.0;
+                    ((next.x - prev.x) * k, (next.y - prev.y) * k)
+                };
+                pairs
+                    .enumerate()
+                    .map(|(i, w)| {
+                        let (t0, t1) = (tangent(i), tangent(i + 1));
+                        vec![PathSegment::CubicTo(
+                            at(w[0].x + t0.0 / 3.0, w[0].y + t0.1 / 3.0),
+                            at(w[1].x - t1.0 / 3.0, w[1].y - t1.1 / 3.0),
+                            w[1],
+                        )]
+                    })
+                    .collect()
+            }
+        }
+    }
+}
+
+/// Tangent at each point that keeps the curve monotone between points
+fn monotone_slopes(points: &[Point]) -> Vec<f64> {
+    let secants: Vec<f64> = points
+        .windows(2)
+        .map(|w| {
+            let dx = w[1].x - w[0].x;
+            if dx.abs() < f64::EPSILON {
+                0.0
+            } else {
+                (w[1].y - w[0].y) / dx
+            }
+        })
+        .collect();
+    if secants.is_empty() {
+        return vec![0.0; points.len()];
+    }
+    let mut slopes = Vec::with_capacity(points.len());
+    slopes.push(secants[0]);
+    for w in secants.windows(2) {
+        slopes.push(if w[0] * w[1] <= 0.0 {
+            0.0
+        } else {
+            (w[0] + w[1]) / 2.0
+        });
+    }
+    slopes.push(secants[secants.len() - 1]);
+    for (i, &secant) in secants.iter().enumerate() {
+        if secant == 0.0 {
+            slopes[i] = 0.0;
+            slopes[i + 1] = 0.0;
+            continue;
+        }
+        let (a, b) = (slopes[i] / secant, slopes[i + 1] / secant);
+        let length = (a * a + b * b).sqrt();
+        if length > 3.0 {
+            slopes[i] = 3.0 / length * a * secant;
+            slopes[i + 1] = 3.0 / length * b * secant;
+        }
+    }
+    slopes
+}
+
+/// Where the series legend goes
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub enum LegendPosition {
+    /// Top right corner of the plot area
+    Inside,
+    /// Right of the plot area, widening the margin
+    Outside,
+}
+
+/// Named block sequence with its own look
+pub struct Series<'a> {
+    name: String,
+    pub(super) blocks: &'a [Block],
+    color: Option<Color>,
+    width: f64,
+    dash: Option<(f64, f64)>,
+    marker: Marker,
+    interpolation: Interpolation,
+}
+
+impl<'a> Series<'a> {
+    pub fn new(name: &str, blocks: &'a [Block]) -> Self {
+        Self {
+            name: name.to_string(),
+            blocks,
+            color: None,
+            width: 2.0,
+            dash: None,
+            marker: Marker::None,
+            interpolation: Interpolation::Linear,
+        }
+    }
+
+    /// Overrides the color picked from the palette
+    pub fn with_color(mut self, color: u32) -> Self {
+        self.color = Some(color.into());
+        self
+    }
+
+    pub fn with_width(mut self, width: f64) -> Self {
+        self.width = width;
+        self
+    }
+
+    pub fn with_dash(mut self, on: f64, off: f64) -> Self {
+        self.dash = Some((on, off));
+        self
+    }
+
+    pub fn with_marker(mut self, marker: Marker) -> Self {
+        self.marker = marker;
+        self
+    }
+
+    pub fn with_interpolation(mut self, interpolation: Interpolation) -> Self {
+        self.interpolation = interpolation;
+        self
+    }
+
+    /// Same look over other blocks
+    pub(super) fn with_blocks<'b>(&self, blocks: &'b [Block]) -> Series<'b> {
+        Series {
+            name: self.name.clone(),
+            blocks,
+            color: self.color,
+            width: self.width,
+            dash: self.dash,
+            marker: self.marker,
+            interpolation: self.interpolation,
+        }
+    }
+}
 
 pub struct Line<'a> {
     size: Dimension,
+    margin: Dimension,
     base: Block,
-    blocks: &'a [Block],
+    series: Vec<Series<'a>>,
+    colors: Option<ColorScale>,
+    palette: Palette,
+    legend: Option<LegendPosition>,
+    baseline: f64,
+    /// Scale position of the baseline
+    origin: f64,
+    /// Base units between the top and the baseline, and below it
+    above: f64,
+    below: f64,
+    x_scale: Option<Scale>,
+    y_scale: Option<Scale>,
+    /// Scale positions at the left and right end of the time axis
+    x_domain: (f64, f64),
+    viewport: Option<Viewport>,
+    /// Blocks of each series clipped to the viewport
+    visible: Option<Vec<Vec<Block>>>,
 }
 
 impl<'a> Line<'a> {
     pub fn new(blocks: &'a [Block]) -> Self {
-        let base = Block(20.0, 20.0);
-        let width = blocks.iter().fold(0.0, |total, block| total + block.0);
-        let maximum = blocks
+        Self::multi(vec![Series::new("", blocks)])
+    }
+
+    /// Several series sharing the same axes
+    pub fn multi(series: Vec<Series<'a>>) -> Self {
+        let mut line = Self {
+            size: Dimension { w: 0.0, h: 0.0 },
+            margin: Dimension { w: 40.0, h: 40.0 },
+            base: Block(20.0, 20.0),
+            series,
+            colors: None,
+            palette: Palette::default(),
+            legend: None,
+            baseline: 0.0,
+            origin: 0.0,
+            above: 0.0,
+            below: 0.0,
+            x_scale: None,
+            y_scale: None,
+            x_domain: (0.0, 1.0),
+            viewport: None,
+            visible: None,
+        };
+        line.resize();
+        line
+    }
+
+    /// Colors each segment by the value it leads to
+    pub fn with_colors(mut self, colors: ColorScale) -> Self {
+        self.colors = Some(colors);
+        self
+    }
+
+    /// Colors for series without one of their own
+    pub fn with_palette(mut self, palette: Palette) -> Self {
+        self.palette = palette;
+        self
+    }
+
+    pub fn with_legend(mut self, position: LegendPosition) -> Self {
+        self.legend = Some(position);
+        self.resize();
+        self
+    }
+
+    /// Value the zero line is drawn at, marked when values fall below it
+    pub fn with_baseline(mut self, baseline: f64) -> Self {
+        self.baseline = baseline;
+        self.resize();
+        self
+    }
+
+    /// Maps block end times onto the horizontal axis, labelled with ticks
+    pub fn with_x_scale(mut self, scale: Scale) -> Self {
+        self.x_scale = Some(scale);
+        self.resize();
+        self
+    }
+
+    /// Maps values onto the vertical axis, labelled with ticks.
+    /// One base block spans one unit of scale position.
+    pub fn with_y_scale(mut self, scale: Scale) -> Self {
+        self.y_scale = Some(scale);
+        self.resize();
+        self
+    }
+
+    /// Draws only the part of each series within the window, its values fixing
+    /// the height. Time stays absolute on the axis.
+    pub fn with_viewport(mut self, viewport: Viewport) -> Self {
+        self.visible = Some(
+            self.series
+                .iter()
+                .map(|x| viewport.clip(x.blocks).1)
+                .collect(),
+        );
+        self.viewport = Some(viewport);
+        self.resize();
+        self
+    }
+
+    /// Blocks drawn of a series, all of them unless there is a viewport
+    fn blocks(&self, idx: usize) -> &[Block] {
+        match &self.visible {
+            Some(visible) => &visible[idx],
+            None => self.series[idx].blocks,
+        }
+    }
+
+    /// Time at the left end of the time axis
+    fn start(&self) -> f64 {
+        self.viewport.map_or(0.0, |x| x.start())
+    }
+
+    fn resize(&mut self) {
+        let all = 0..self.series.len();
+        let duration = all
+            .clone()
+            .map(|idx| {
+                self.blocks(idx)
+                    .iter()
+                    .fold(0.0, |total, block| total + block.0)
+            })
+            .fold(0.0, f64::max);
+        assert!(
+            all.clone().any(|idx| !self.blocks(idx).is_empty()),
+            "there has to be maximum"
+        );
+        let range = match self.viewport.and_then(|x| x.values()) {
+            Some((lo, hi)) => (self.y(lo), self.y(hi)),
+            None => all
+                .clone()
+                .flat_map(|idx| self.blocks(idx).iter().map(|x| self.y(x.1)))
+                .filter(|x| x.is_finite())
+                .map(|x| (x, x))
+                .reduce(|(lo, hi), (v, _)| (lo.min(v), hi.max(v)))
+                .unwrap_or((0.0, 1.0)),
+        };
+        self.origin = match self.y(self.baseline) {
+            origin if origin.is_finite() => origin,
+            _ => range.0.floor(),
+        };
+        (self.above, self.below) = extent(range, self.origin);
+        self.x_domain = match &self.x_scale {
+            Some(scale) => {
+                let start = all
+                    .flat_map(|idx| {
+                        self.blocks(idx).iter().scan(self.start(), |t, block| {
+                            *t += block.0;
+                            Some(scale.forward(*t))
+                        })
+                    })
+                    .chain(std::iter::once(scale.forward(self.start())))
+                    .filter(|x| x.is_finite())
+                    .fold(f64::MAX, f64::min);
+                (start, scale.forward(self.start() + duration))
+            }
+            None => (0.0, duration),
+        };
+        let height = (self.above + self.below) * self.base.1;
+        let mut mw = match self.legend {
+            Some(LegendPosition::Outside) => 40.0f64.max(self.legend_size().w + CHAR_WIDTH * 2.0),
+            _ => 40.0,
+        };
+        if let Some(longest) = self.y_ticks().iter().map(|x| label(x.1).len()).max() {
+            mw = mw.max((longest as f64 + 2.0) * CHAR_WIDTH);
+        }
+        self.margin.w = mw;
+        self.size = Dimension {
+            w: duration * self.pixels(),
+            h: height,
+        };
+        let &Block(dw, dh) = self.padding();
+        let &Dimension { w: mw, h: mh } = self.margin();
+        self.size.w += (self.base.0 * dw * 2.0) + mw * 2.0;
+        self.size.h += (self.base.1 * dh * 2.0) + mh * 2.0;
+    }
+
+    /// Scale position of a value
+    fn y(&self, value: f64) -> f64 {
+        scaled(self.y_scale.as_ref(), value)
+    }
+
+    /// Scale positions and values of the vertical axis labels
+    fn y_ticks(&self) -> Vec<(f64, f64)> {
+        match &self.y_scale {
+            Some(scale) => ticks(
+                scale,
+                (self.origin - self.below, self.origin + self.above),
+                (self.above + self.below) * self.base.1,
+                CHAR_HEIGHT * 2.0,
+            ),
+            None => Vec::new(),
+        }
+    }
+
+    /// Offsets from the left of the plot area and times of the horizontal axis labels
+    fn x_ticks(&self) -> Vec<(f64, f64)> {
+        match &self.x_scale {
+            Some(scale) => ticks(scale, self.x_domain, self.width(), CHAR_WIDTH * 10.0)
+                .into_iter()
+                .map(|(_, time)| (self.x(time), time))
+                .collect(),
+            None => Vec::new(),
+        }
+    }
+
+    /// Pixels per block duration unit, one at the default base
+    fn pixels(&self) -> f64 {
+        self.base.0 / 20.0
+    }
+
+    /// Length of the time axis
+    fn width(&self) -> f64 {
+        let &Block(dw, _) = self.padding();
+        let &Dimension { w: mw, .. } = self.margin();
+        self.size.w - ((self.base.0 * dw) + mw) * 2.0
+    }
+
+    /// Offset of a time from the left of the plot area, scaled times spanning the
+    /// width linear times would take
+    fn x(&self, time: f64) -> f64 {
+        match &self.x_scale {
+            Some(scale) => {
+                let (lo, hi) = self.x_domain;
+                let offset = (scale.forward(time) - lo) / (hi - lo) * self.width();
+                if offset.is_nan() {
+                    0.0
+                } else {
+                    offset.clamp(0.0, self.width())
+                }
+            }
+            None => (time - self.start()) * self.pixels(),
+        }
+    }
+
+    fn axes(&self) -> Vec<Renderable> {
+        let &Block(dw, dh) = self.padding();
+        let &Dimension { w: mw, h: mh } = self.margin();
+        let left = (self.base.0 * dw) + mw;
+        let bottom = self.size.h - ((self.base.1 * dh) + mh);
+        let zero = self.baseline_y();
+        let mut axes = Vec::new();
+        for (position, value) in self.y_ticks() {
+            let y = zero - (position - self.origin) * self.base.1;
+            axes.append(&mut left_tick(left, y, value));
+        }
+        for (offset, time) in self.x_ticks() {
+            axes.append(&mut bottom_tick(left + offset, bottom, time));
+        }
+        axes
+    }
+
+    pub(super) fn color(&self, idx: usize) -> Color {
+        self.series[idx]
+            .color
+            .unwrap_or_else(|| self.palette.color(idx))
+    }
+
+    pub(super) fn name(&self, idx: usize) -> &str {
+        &self.series[idx].name
+    }
+
+    fn style(&self, idx: usize, color: Color) -> Style {
+        let series = &self.series[idx];
+        let style = Style::from(color).with_border(series.width);
+        match series.dash {
+            Some((on, off)) => style.with_dash(on, off),
+            None => style,
+        }
+    }
+
+    /// Edge of every series as drawn, following its interpolation
+    pub(super) fn series_edges(&self) -> Vec<Vec<Point>> {
+        self.series
             .iter()
-            .map(|x| x.1 * base.1)
-            .reduce(f64::max)
-            .expect("there has to be maximum");
-        let height = ((maximum / base.1).floor() + 1.0) * base.1;
-        let mut roll = Self {
-            size: Dimension {
-                w: width,
-                h: height,
+            .enumerate()
+            .map(|(idx, series)| {
+                let points = self.points(self.blocks(idx));
+                match points.first() {
+                    Some(&first) => {
+                        let mut path = vec![PathSegment::MoveTo(first)];
+                        path.extend(series.interpolation.segments(&points).into_iter().flatten());
+                        flatten(&path)
+                    }
+                    None => points,
+                }
+            })
+            .collect()
+    }
+
+    /// Vertical position of the baseline value
+    pub(super) fn baseline_y(&self) -> f64 {
+        let &Block(_, dh) = self.padding();
+        let &Dimension { h: mh, .. } = self.margin();
+        (self.base.1 * dh) + mh + self.above * self.base.1
+    }
+
+    /// Point at the end of each block
+    fn points(&self, blocks: &[Block]) -> Vec<Point> {
+        let &Block(dw, dh) = self.padding();
+        let &Dimension { w: mw, h: mh } = self.margin();
+        let (left, top) = ((self.base.0 * dw) + mw, (self.base.1 * dh) + mh);
+        let zero = self.baseline_y();
+        let mut time = self.start();
+        blocks
+            .iter()
+            .map(|block| {
+                time += block.0;
+                let position = match self.y(block.1) {
+                    position if position.is_nan() => f64::NEG_INFINITY,
+                    position => position,
+                };
+                Point {
+                    x: left + self.x(time),
+                    y: (zero - (position - self.origin) * self.base.1)
+                        .clamp(top, self.size.h - top),
+                }
+            })
+            .collect()
+    }
+
+    fn marker(&self, at: Point, marker: Marker, color: Color) -> Vec<Renderable> {
+        let r = 3.0;
+        let style = Style::from(color);
+        match marker {
+            Marker::None => Vec::new(),
+            Marker::Square => vec![Renderable::Rect(
+                Point {
+                    x: at.x - r,
+                    y: at.y - r,
+                },
+                Dimension {
+                    w: r * 2.0,
+                    h: r * 2.0,
+                },
+                style,
+            )],
+            Marker::Diamond => vec![Renderable::Polygon(
+                vec![
+                    Point {
+                        x: at.x,
+                        y: at.y - r,
+                    },
+                    Point {
+                        x: at.x + r,
+                        y: at.y,
+                    },
+                    Point {
+                        x: at.x,
+                        y: at.y + r,
+                    },
+                    Point {
+                        x: at.x - r,
+                        y: at.y,
+                    },
+                ],
+                style,
+            )],
+            Marker::Cross => vec![
+                Renderable::Line(
+                    Point {
+                        x: at.x - r,
+                        y: at.y - r,
+                    },
+                    Point {
+                        x: at.x + r,
+                        y: at.y + r,
+                    },
+                    style,
+                ),
+                Renderable::Line(
+                    Point {
+                        x: at.x - r,
+                        y: at.y + r,
+                    },
+                    Point {
+                        x: at.x + r,
+                        y: at.y - r,
+                    },
+                    style,
+                ),
+            ],
+        }
+    }
+
+    /// Legend box size, one row per named series
+    fn legend_size(&self) -> Dimension {
+        let names = self.series.iter().filter(|x| !x.name.is_empty());
+        let longest = names.clone().map(|x| x.name.chars().count()).max();
+        match longest {
+            Some(chars) => Dimension {
+                w: CHAR_WIDTH * (chars as f64 + 7.0),
+                h: CHAR_HEIGHT * 2.0 * names.count() as f64 + CHAR_HEIGHT,
+            },
+            None => Dimension { w: 0.0, h: 0.0 },
+        }
+    }
+
+    fn legend(&self, position: LegendPosition) -> Vec<Renderable> {
+        let size = self.legend_size();
+        if size.w == 0.0 {
+            return Vec::new();
+        }
+        let &Dimension { w: mw, h: mh } = self.margin();
+        let corner = match position {
+            LegendPosition::Inside => Point {
+                x: self.size.w - mw - size.w - CHAR_WIDTH,
+                y: mh + CHAR_HEIGHT,
+            },
+            LegendPosition::Outside => Point {
+                x: self.size.w - mw + CHAR_WIDTH,
+                y: mh,
             },
-            base,
-            blocks,
         };
-        let &Block(dw, dh) = roll.padding();
-        let &Dimension { w: mw, h: mh } = roll.margin();
-        roll.size.w += (roll.base.0 * dw * 2.0) + mw * 2.0;
-        roll.size.h += (roll.base.1 * dh * 2.0) + mh * 2.0;
+        let mut legend = vec![Renderable::Rect(
+            corner,
+            size,
+            Style::color(0x303030)
+                .with_border(1.0)
+                .with_background(0x060910),
+        )];
+        let named = self
+            .series
+            .iter()
+            .enumerate()
+            .filter(|(_, x)| !x.name.is_empty());
+        for (row, (idx, series)) in named.enumerate() {
+            let y = corner.y + CHAR_HEIGHT * (2.0 * row as f64 + 1.0);
+            let color = self.color(idx);
+            let sample = (
+                Point {
+                    x: corner.x + CHAR_WIDTH,
+                    y: y + CHAR_HEIGHT / 2.0,
+                },
+                Point {
+                    x: corner.x + CHAR_WIDTH * 4.0,
+                    y: y + CHAR_HEIGHT / 2.0,
+                },
+            );
+            legend.push(Renderable::Line(sample.0, sample.1, self.style(idx, color)));
+            legend.append(&mut self.marker(
+                Point {
+                    x: (sample.0.x + sample.1.x) / 2.0,
+                    y: sample.0.y,
+                },
+                series.marker,
+                color,
+            ));
+            legend.push(Renderable::Text(
+                Point {
+                    x: corner.x + CHAR_WIDTH * 5.0,
+                    y,
+                },
+                series.name.clone(),
+                Style::color(0x909090),
+            ));
+        }
+        legend
+    }
+}
 
-        roll
+impl<'a> Scalable for Line<'a> {
+    fn with_base(mut self, base: Block) -> Self {
+        self.base = base;
+        self.resize();
+        self
+    }
+}
+
+impl<'a> Plot for Line<'a> {
+    fn position(&self, time: f64, value: f64) -> Point {
+        let (area, _) = self.area();
+        Point {
+            x: area.x + self.x(time),
+            y: self.baseline_y() - (self.y(value) - self.origin) * self.base.1,
+        }
     }
 }
 
@@ -43,6 +714,10 @@ impl<'a> Graph for Line<'a> {
         &self.base
     }
 
+    fn margin(&self) -> &Dimension {
+        &self.margin
+    }
+
     fn draw<T>(&self, renderer: &mut T) -> Vec<u8>
     where
         T: ShapeRenderer,
@@ -55,49 +730,314 @@ impl<'a> Graph for Line<'a> {
     }
 
     fn renderables(&self) -> Vec<Renderable> {
-        let &Dimension {
-            h: height,
-            w: _width,
-        } = self.size();
-        let &Block(dw, dh) = self.padding();
-        let &Dimension { w: mw, h: mh } = self.margin();
-        let vpad = (self.base.1 * dh) + mh;
-        let hpad = (self.base.0 * dw) + mw;
-        let mut prev = Point { x: hpad, y: vpad };
-        let style = Style::color(0x6495ED).with_border(2.0);
+        self.renderables_over(Vec::new())
+    }
+}
+
+impl<'a> Line<'a> {
+    /// Renderables with shapes drawn under the series, such as the fills of
+    /// an area, all in the data group
+    pub(super) fn renderables_over(&self, mut data: Vec<Renderable>) -> Vec<Renderable> {
         let mut renderables = self.grid();
-        renderables.append(
-            &mut self
-                .blocks
-                .iter()
-                .filter_map(|block| {
-                    let mut delta_y = block.1 * self.base.1;
-                    delta_y = (height - vpad) - delta_y;
-                    let rect = if prev.x != hpad {
-                        Some(Renderable::Line(
-                            Point {
-                                x: prev.x,
-                                y: prev.y,
-                            },
-                            Point {
-                                x: prev.x + block.0,
-                                y: delta_y,
-                            },
-                            style,
-                        ))
-                    } else {
-                        None
-                    };
-
-                    prev.x += block.0;
-                    prev.y = delta_y;
-                    rect
+        let &Dimension { w: mw, h: mh } = self.margin();
+        let zero = self.baseline_y();
+        if zero < self.size.h - mh {
+            renderables.push(Renderable::Rect(
+                Point { x: mw, y: zero },
+                Dimension {
+                    w: self.size.w - mw * 2.0,
+                    h: 1.0,
+                },
+                Style::color(0x909090),
+            ));
+        }
+        for (idx, series) in self.series.iter().enumerate() {
+            let color = self.color(idx);
+            let style = self.style(idx, color);
+            let scale = self
+                .colors
+                .clone()
+                .map(|colors| colors.fit(value_range(self.blocks(idx))));
+            let points = self.points(self.blocks(idx));
+            let mut shapes = Vec::new();
+            if series.interpolation == Interpolation::Linear {
+                for (pair, block) in points.windows(2).zip(self.blocks(idx).iter().skip(1)) {
+                    shapes.push(Renderable::Line(
+                        pair[0],
+                        pair[1],
+                        match &scale {
+                            Some(scale) => self.style(idx, scale.color(block.1)),
+                            None => style,
+                        },
+                    ));
+                }
+            } else if !points.is_empty() {
+                let segments = series.interpolation.segments(&points);
+                match &scale {
+                    Some(scale) => {
+                        for ((from, path), block) in points
+                            .iter()
+                            .zip(segments)
+                            .zip(self.blocks(idx).iter().skip(1))
+                        {
+                            let mut path = path;
+                            path.insert(0, PathSegment::MoveTo(*from));
+                            shapes.push(Renderable::Path(
+                                path,
+                                self.style(idx, scale.color(block.1)),
+                            ));
+                        }
+                    }
+                    None => {
+                        let mut path = vec![PathSegment::MoveTo(points[0])];
+                        path.extend(segments.into_iter().flatten());
+                        shapes.push(Renderable::Path(path, style));
+                    }
+                }
+            }
+            for (point, block) in points.into_iter().zip(self.blocks(idx)) {
+                let meta = Meta::class("point")
+                    .with_data("duration", block.0)
+                    .with_data("intensity", block.1);
+                let mut marker = self.marker(point, series.marker, color);
+                match marker.len() {
+                    0 => {}
+                    1 => shapes.push(marker.remove(0).with_meta(meta)),
+                    _ => shapes.push(Renderable::Group(marker, meta)),
+                }
+            }
+            data.push(Renderable::Group(
+                shapes,
+                Meta::class("series").with_data("series", &series.name),
+            ));
+        }
+        renderables.push(Renderable::Group(data, Meta::class("data")));
+        renderables.append(&mut self.axes());
+        if let (Some(viewport), None) = (&self.viewport, &self.x_scale) {
+            let longest = (0..self.series.len())
+                .map(|idx| self.blocks(idx))
+                .max_by(|a, b| {
+                    let duration = |x: &[Block]| x.iter().map(|x| x.0).sum::<f64>();
+                    duration(a).total_cmp(&duration(b))
                 })
-                .collect::<Vec<Renderable>>(),
-        );
+                .unwrap_or(&[]);
+            renderables.append(&mut viewport.labels(longest, mw, self.size.w - mw, mh));
+        }
+        if let Some(position) = self.legend {
+            renderables.append(&mut self.legend(position));
+        }
         renderables
     }
 }
 
 #[cfg(test)]
-mod tests {}
+mod tests {
+    use super::*;
+    use crate::graph::data;
+
+    /// Shapes of each series
+    fn series(line: &Line) -> Vec<Vec<Renderable>> {
+        data(line.renderables())
+            .into_iter()
+            .map(|x| match x {
+                Renderable::Group(shapes, _) => shapes,
+                _ => panic!("expected series"),
+            })
+            .collect()
+    }
+
+    #[test]
+    fn series_share_axes() {
+        let low = [Block(4.0, 1.0), Block(4.0, 2.0)];
+        let high = [Block(2.0, 5.0), Block(2.0, 1.0), Block(8.0, 3.0)];
+        let line = Line::multi(vec![Series::new("low", &low), Series::new("high", &high)]);
+
+        assert_eq!(line.size.w, 12.0 + 80.0, "longest series");
+        assert_eq!(line.size.h, 6.0 * 20.0 + 80.0, "highest value");
+        assert_eq!(
+            series(&line).iter().map(|x| x.len()).collect::<Vec<_>>(),
+            vec![1, 2],
+            "segments"
+        );
+    }
+
+    #[test]
+    fn viewport_keeps_absolute_time() {
+        let blocks: Vec<Block> = (0..40).map(|x| Block(10.0, (x % 4) as f64)).collect();
+        let line = Line::new(&blocks)
+            .with_viewport(Viewport::new(95.0, 135.0).with_values(0.0, 8.0))
+            .with_x_scale(Scale::Linear);
+        let points = line.series_edges();
+        let times: Vec<f64> = line.x_ticks().iter().map(|x| x.1).collect();
+
+        assert_eq!(line.size.w, 40.0 + 80.0);
+        assert_eq!(line.size.h, 9.0 * 20.0 + 80.0, "fixed value range");
+        assert_eq!(points[0].len(), 5, "partial blocks at both edges");
+        assert_eq!(points[0][0].x, line.margin.w + 5.0);
+        assert_eq!(times, vec![100.0, 120.0]);
+    }
+
+    #[test]
+    fn bipolar_values_centered() {
+        let bend = [Block(4.0, -2.0), Block(4.0, 0.5), Block(4.0, 1.0)];
+        let line = Line::new(&bend);
+        let points = line.points(&bend);
+
+        assert_eq!(line.size.h, 2.0 * 3.0 * 20.0 + 80.0, "room for both sides");
+        assert_eq!(line.baseline_y(), 40.0 + 60.0, "zero in the middle");
+        assert_eq!(points[0].y, 100.0 + 40.0);
+        assert_eq!(points[2].y, 100.0 - 20.0);
+        assert_eq!(
+            line.renderables().len(),
+            line.grid().len() + 1 + 1,
+            "baseline"
+        );
+        assert_eq!(series(&line)[0].len(), 2);
+    }
+
+    #[test]
+    fn log_scale_in_decades() {
+        let spectrum = [Block(4.0, 20.0), Block(4.0, 200.0), Block(4.0, 20000.0)];
+        let line = Line::new(&spectrum).with_y_scale(Scale::Log10);
+        let points = line.points(&spectrum);
+        let labels: Vec<String> = line
+            .axes()
+            .into_iter()
+            .filter_map(|x| match x {
+                Renderable::Text(_, text, _) => Some(text),
+                _ => None,
+            })
+            .collect();
+
+        assert_eq!(
+            line.size.h,
+            4.0 * 20.0 + 80.0,
+            "one block per decade from 10"
+        );
+        assert!(
+            (points[0].y - points[1].y - 20.0).abs() < 1e-9,
+            "a decade apart"
+        );
+        assert_eq!(labels, vec!["10", "100", "1000", "10000", "100000"]);
+    }
+
+    #[test]
+    fn scaled_time_keeps_width() {
+        let blocks = [Block(10.0, 1.0), Block(90.0, 1.0), Block(900.0, 1.0)];
+        let linear = Line::new(&blocks);
+        let line = Line::new(&blocks).with_x_scale(Scale::Log10);
+        let xs: Vec<f64> = line.points(&blocks).iter().map(|p| p.x).collect();
+
+        assert_eq!(line.size.w, linear.size.w);
+        assert_eq!(xs, vec![40.0, 40.0 + 500.0, 40.0 + 1000.0]);
+        assert!(!line.x_ticks().is_empty());
+    }
+
+    #[test]
+    fn palette_and_overrides() {
+        let blocks = [Block(4.0, 1.0), Block(4.0, 2.0)];
+        let line = Line::multi(vec![
+            Series::new("a", &blocks),
+            Series::new("b", &blocks).with_color(0xFF0000),
+        ])
+        .with_palette(Palette::new(&[0x00FF00]));
+
+        assert_eq!(line.color(0), Color::from(0x00FF00));
+        assert_eq!(line.color(1), Color::from(0xFF0000));
+    }
+
+    #[test]
+    fn markers_and_dashes() {
+        let blocks = [Block(4.0, 1.0), Block(4.0, 2.0)];
+        let line = Line::multi(vec![Series::new("a", &blocks)
+            .with_marker(Marker::Cross)
+            .with_dash(4.0, 2.0)]);
+        let shapes = &series(&line)[0];
+
+        assert_eq!(shapes.len(), 1 + 2, "segment and crosses");
+        match &shapes[0] {
+            Renderable::Line(_, _, style) => assert_eq!(style.get_dash(), Some((4.0, 2.0))),
+            _ => panic!("expected segment"),
+        }
+        match &shapes[2] {
+            Renderable::Group(cross, meta) => {
+                assert_eq!(cross.len(), 2);
+                assert_eq!(meta.get_classes(), ["point"]);
+                assert_eq!(
+                    meta.get_data()[1],
+                    ("intensity".to_string(), "2".to_string())
+                );
+            }
+            _ => panic!("expected tagged cross"),
+        }
+    }
+
+    fn at(x: f64, y: f64) -> Point {
+        Point { x, y }
+    }
+
+    #[test]
+    fn step_modes() {
+        let points = [at(0.0, 0.0), at(10.0, 20.0)];
+        let corner = |mode: Interpolation, n: usize| match mode.segments(&points)[0][n] {
+            PathSegment::LineTo(p) => (p.x, p.y),
+            _ => panic!("expected straight step"),
+        };
+
+        assert_eq!(corner(Interpolation::StepBefore, 0), (0.0, 20.0));
+        assert_eq!(corner(Interpolation::StepAfter, 0), (10.0, 0.0));
+        assert_eq!(corner(Interpolation::StepMiddle, 0), (5.0, 0.0));
+        assert_eq!(corner(Interpolation::StepMiddle, 1), (5.0, 20.0));
+    }
+
+    #[test]
+    fn monotone_does_not_overshoot() {
+        let points = [at(0.0, 0.0), at(10.0, 10.0), at(20.0, 10.0), at(30.0, 40.0)];
+        for (w, path) in points
+            .windows(2)
+            .zip(Interpolation::Monotone.segments(&points))
+        {
+            let (lo, hi) = (w[0].y.min(w[1].y), w[0].y.max(w[1].y));
+            match path[..] {
+                [PathSegment::CubicTo(c1, c2, end)] => {
+                    assert!(lo <= c1.y && c1.y <= hi && lo <= c2.y && c2.y <= hi);
+                    assert_eq!((end.x, end.y), (w[1].x, w[1].y));
+                }
+                _ => panic!("expected single cubic"),
+            }
+        }
+    }
+
+    #[test]
+    fn spline_paths_through_points() {
+        let blocks = [Block(4.0, 1.0), Block(4.0, 3.0), Block(4.0, 2.0)];
+        let line = Line::multi(vec![
+            Series::new("a", &blocks).with_interpolation(Interpolation::CatmullRom)
+        ]);
+        let shapes = &series(&line)[0];
+
+        assert_eq!(shapes.len(), 1, "single path");
+        match &shapes[0] {
+            Renderable::Path(path, _) => assert_eq!(path.len(), 3, "move and two curves"),
+            _ => panic!("expected path"),
+        }
+        let straight = Interpolation::Cardinal(1.0).segments(&line.points(&blocks));
+        match straight[0][..] {
+            [PathSegment::CubicTo(c1, _, _)] => assert_eq!(c1.x, line.points(&blocks)[0].x),
+            _ => panic!("expected single cubic"),
+        }
+    }
+
+    #[test]
+    fn legend_outside_widens_margin() {
+        let blocks = [Block(4.0, 1.0)];
+        let inside =
+            Line::multi(vec![Series::new("velocity", &blocks)]).with_legend(LegendPosition::Inside);
+        let outside = Line::multi(vec![Series::new("velocity", &blocks)])
+            .with_legend(LegendPosition::Outside);
+
+        assert_eq!(inside.margin.w, 40.0);
+        assert!(outside.margin.w > 40.0);
+        assert!(outside.size.w > inside.size.w);
+    }
+}
@@ -5,9 +5,177 @@ pub use hits::Hits;
 mod track;
 pub use track::Track;
 mod line;
-pub use line::Line;
+pub use line::{Interpolation, LegendPosition, Line, Marker, Series};
+mod heatmap;
+pub use heatmap::Heatmap;
+mod waveform;
+pub use waveform::{Samples, Waveform};
+mod spectrogram;
+pub use spectrogram::Spectrogram;
+mod envelope;
+pub use envelope::{Breakpoint, Curve, Envelope};
+mod area;
+pub use area::{Area, StackedArea};
+mod bar;
+pub use bar::{Bar, BarLayout, Orientation};
+mod histogram;
+pub use histogram::{Binning, Histogram};
+mod boxplot;
+pub use boxplot::BoxPlot;
+mod canvas;
+pub use canvas::Canvas;
+mod viewport;
+pub use viewport::Viewport;
+mod systems;
+pub use systems::{Page, Systems};
+mod annotation;
+pub use annotation::{Annotated, Annotation};
+mod playhead;
+pub use playhead::Playhead;
 
-use crate::{Block, Dimension, Point, Renderable, ShapeRenderer, Style};
+use crate::scale::{steps, Scale};
+use crate::{Block, Dimension, Meta, Point, Renderable, ShapeRenderer, Style};
+
+/// Approximate text extents, in both raster and vector output
+const CHAR_WIDTH: f64 = 6.0;
+const CHAR_HEIGHT: f64 = 7.0;
+
+/// Value formatted for labels, with at most two decimals
+fn label(value: f64) -> String {
+    let text = format!("{:.2}", value);
+    let text = text.trim_end_matches('0').trim_end_matches('.');
+    if text == "-0" {
+        "0".to_string()
+    } else {
+        text.to_string()
+    }
+}
+
+/// Lowest and highest block value
+fn value_range(blocks: &[Block]) -> (f64, f64) {
+    blocks
+        .iter()
+        .map(|x| (x.1, x.1))
+        .reduce(|(lo, hi), (v, _)| (lo.min(v), hi.max(v)))
+        .unwrap_or((0.0, 1.0))
+}
+
+/// Scale position of a value, the value itself without a scale
+fn scaled(scale: Option<&Scale>, value: f64) -> f64 {
+    scale.map_or(value, |scale| scale.forward(value))
+}
+
+/// Lowest and highest scale position of the block values, leaving out those
+/// the scale is undefined at
+fn scaled_range(blocks: &[Block], scale: Option<&Scale>) -> (f64, f64) {
+    blocks
+        .iter()
+        .map(|x| scaled(scale, x.1))
+        .filter(|x| x.is_finite())
+        .map(|x| (x, x))
+        .reduce(|(lo, hi), (v, _)| (lo.min(v), hi.max(v)))
+        .unwrap_or((0.0, 1.0))
+}
+
+/// Round scale positions within `lo..=hi` and their values, about `spacing`
+/// pixels apart along `length` pixels
+fn ticks(scale: &Scale, (lo, hi): (f64, f64), length: f64, spacing: f64) -> Vec<(f64, f64)> {
+    let count = (length / spacing).max(2.0) as usize;
+    steps(lo, hi, count)
+        .into_iter()
+        .map(|position| (position, scale.inverse(position)))
+        .collect()
+}
+
+/// Tick mark and value label left of the plot area, at height `y`
+fn left_tick(left: f64, y: f64, value: f64) -> Vec<Renderable> {
+    let style = Style::color(0x909090);
+    let text = label(value);
+    vec![
+        Renderable::Rect(
+            Point { x: left - 4.0, y },
+            Dimension { w: 4.0, h: 1.0 },
+            style,
+        ),
+        Renderable::Text(
+            Point {
+                x: left - 5.0 - text.len() as f64 * CHAR_WIDTH,
+                y: y - CHAR_HEIGHT / 2.0,
+            },
+            text,
+            style,
+        ),
+    ]
+}
+
+/// Tick mark and value label below the plot area, at `x`
+fn bottom_tick(x: f64, bottom: f64, value: f64) -> Vec<Renderable> {
+    let style = Style::color(0x909090);
+    let text = label(value);
+    vec![
+        Renderable::Rect(Point { x, y: bottom }, Dimension { w: 1.0, h: 4.0 }, style),
+        Renderable::Text(
+            Point {
+                x: x - text.len() as f64 * CHAR_WIDTH / 2.0,
+                y: bottom + CHAR_HEIGHT,
+            },
+            text,
+            style,
+        ),
+    ]
+}
+
+/// Whole base units needed above and below the baseline to fit the range,
+/// the same on both sides when values cross it
+fn extent(range: (f64, f64), baseline: f64) -> (f64, f64) {
+    let (lo, hi) = range;
+    let above = if hi >= baseline {
+        (hi - baseline).floor() + 1.0
+    } else {
+        0.0
+    };
+    let below = if lo < baseline {
+        (baseline - lo).floor() + 1.0
+    } else {
+        0.0
+    };
+    if above > 0.0 && below > 0.0 {
+        (above.max(below), above.max(below))
+    } else {
+        (above, below)
+    }
+}
+
+/// Whole pixel positions of grid lines from `from` up to `to`, one per base cell,
+/// skipping cells when they get too small to tell apart
+fn gridlines(from: f64, to: f64, base: f64) -> Vec<f64> {
+    let (from, to) = (from.max(0.0).floor(), to.max(0.0).floor());
+    let step = base * (4.0 / base).ceil().max(1.0);
+    if !step.is_finite() || step <= 0.0 {
+        return Vec::new();
+    }
+    (0..)
+        .map(|i| (from + i as f64 * step).floor())
+        .take_while(|&x| x < to)
+        .collect()
+}
+
+/// Linearly interpolated quantile of sorted values, `q` within 0..=1
+fn quantile(sorted: &[f64], q: f64) -> f64 {
+    if sorted.is_empty() {
+        return f64::NAN;
+    }
+    let position = q.clamp(0.0, 1.0) * (sorted.len() - 1) as f64;
+    let (below, above) = (position.floor() as usize, position.ceil() as usize);
+    sorted[below] + (sorted[above] - sorted[below]) * (position - below as f64)
+}
+
+/// Finite values in ascending order
+fn sorted(values: &[f64]) -> Vec<f64> {
+    let mut sorted: Vec<f64> = values.iter().cloned().filter(|x| x.is_finite()).collect();
+    sorted.sort_by(|a, b| a.total_cmp(b));
+    sorted
+}
 
 pub trait Graph {
     fn renderables(&self) -> Vec<Renderable>;
@@ -28,6 +196,7 @@ pub trait Graph {
         &Block(0.0, 0.0)
     }
 
+    /// Background and gridlines, as a single group
     fn grid(&self) -> Vec<Renderable> {
         let &Dimension { w: mw, h: mh } = self.margin();
         let &Dimension {
@@ -45,9 +214,9 @@ pub trait Graph {
         )];
         let style = Style::color(0x303030);
 
-        for y in ((mh as usize)..((height - mh) as usize)).step_by(baseh as usize) {
+        for y in gridlines(mh, height - mh, baseh) {
             grid.push(Renderable::Rect(
-                Point { x: mw, y: y as f64 },
+                Point { x: mw, y },
                 Dimension {
                     w: width - mw * 2.0,
                     h: 1.0,
@@ -66,9 +235,9 @@ pub trait Graph {
             },
             style,
         ));
-        for x in ((mw as usize)..((width - mw) as usize)).step_by(basew as usize) {
+        for x in gridlines(mw, width - mw, basew) {
             grid.push(Renderable::Rect(
-                Point { x: x as f64, y: mh },
+                Point { x, y: mh },
                 Dimension {
                     w: 1.0,
                     h: height - mh * 2.0,
@@ -88,6 +257,51 @@ pub trait Graph {
             style,
         ));
 
-        grid
+        vec![Renderable::Group(grid, Meta::class("grid"))]
     }
 }
+
+/// Graphs laid out on a grid of base cells, which can be resized
+pub trait Scalable: Graph + Sized {
+    /// Same graph drawn with other base cells
+    fn with_base(self, base: Block) -> Self;
+}
+
+/// Graphs plotting values over time, which overlays can be placed on
+pub trait Plot: Graph {
+    /// Where a value at a time is drawn, time being absolute in duration units
+    fn position(&self, time: f64, value: f64) -> Point;
+
+    /// Top left corner and size of the area inside margins and padding
+    fn area(&self) -> (Point, Dimension) {
+        let &Block(dw, dh) = self.padding();
+        let &Dimension { w: mw, h: mh } = self.margin();
+        let &Dimension { w, h } = self.size();
+        let (left, top) = ((self.base().0 * dw) + mw, (self.base().1 * dh) + mh);
+        (
+            Point { x: left, y: top },
+            Dimension {
+                w: w - left * 2.0,
+                h: h - top * 2.0,
+            },
+        )
+    }
+
+    /// Index of the first renderable drawn over the grid, where overlays
+    /// beneath the data go
+    fn data_start(&self) -> usize {
+        self.grid().len()
+    }
+}
+
+/// Shapes in the data group, for tests to look into
+#[cfg(test)]
+pub(super) fn data(renderables: Vec<Renderable>) -> Vec<Renderable> {
+    renderables
+        .into_iter()
+        .find_map(|x| match x {
+            Renderable::Group(shapes, meta) if meta.get_classes() == ["data"] => Some(shapes),
+            _ => None,
+        })
+        .expect("there has to be a data group")
+}
@@ -0,0 +1,227 @@
+use super::{Graph, Plot};
+use crate::svg::{self, Sweep};
+use crate::{Block, Color, Dimension, Point, Renderable, ShapeRenderer, Style};
+
+/// Playback position over a graph, whose renderables are worked out once so
+/// moving the cursor only redraws the overlay
+pub struct Playhead<G> {
+    graph: G,
+    renderables: Vec<Renderable>,
+    time: f64,
+    color: Color,
+    tint: Option<Color>,
+    highlight: Option<Color>,
+}
+
+impl<G: Plot> Playhead<G> {
+    pub fn new(graph: G) -> Self {
+        Self {
+            renderables: graph.renderables(),
+            graph,
+            time: 0.0,
+            color: Color::from(0xFFFFFF),
+            tint: None,
+            highlight: None,
+        }
+    }
+
+    pub fn with_color(mut self, color: u32) -> Self {
+        self.color = color.into();
+        self
+    }
+
+    /// Shades what has been played so far
+    pub fn with_tint(mut self, color: u32) -> Self {
+        self.tint = Some(Color::from(color).with_alpha(64));
+        self
+    }
+
+    /// Lights up notes as the playhead crosses them, in animated SVG
+    pub fn with_highlight(mut self, color: u32) -> Self {
+        self.highlight = Some(color.into());
+        self
+    }
+
+    pub fn with_time(mut self, time: f64) -> Self {
+        self.seek(time);
+        self
+    }
+
+    /// Moves the cursor to an absolute time in duration units
+    pub fn seek(&mut self, time: f64) {
+        self.time = time;
+    }
+
+    pub fn time(&self) -> f64 {
+        self.time
+    }
+
+    pub fn graph(&self) -> &G {
+        &self.graph
+    }
+
+    /// Cursor and tint alone, to be drawn over the graph
+    pub fn overlay(&self) -> Vec<Renderable> {
+        let (area, size) = self.graph.area();
+        let x = self
+            .graph
+            .position(self.time, 0.0)
+            .x
+            .clamp(area.x, area.x + size.w);
+        let mut overlay = Vec::new();
+        if let Some(tint) = self.tint {
+            overlay.push(Renderable::Rect(
+                area,
+                Dimension {
+                    w: x - area.x,
+                    h: size.h,
+                },
+                Style::from(tint),
+            ));
+        }
+        overlay.push(Renderable::Rect(
+            Point {
+                x: x - 1.0,
+                y: area.y,
+            },
+            Dimension { w: 2.0, h: size.h },
+            Style::from(self.color),
+        ));
+        overlay
+    }
+
+    /// Draws the graph alone, for frames to start from
+    pub fn background<T: ShapeRenderer>(&self, mut renderer: T) -> T {
+        for rect in self.renderables.iter().cloned() {
+            renderer.draw(rect);
+        }
+        renderer
+    }
+
+    /// SVG of the graph with the playhead crossing it in `seconds`, looping
+    pub fn animate(&self, seconds: f64) -> svg::Renderer {
+        let sweep = Sweep {
+            color: self.color,
+            tint: self.tint,
+            highlight: self.highlight,
+            ..Sweep::new(self.graph.area(), seconds)
+        };
+        self.background(svg::Renderer::new(self.size()).with_sweep(sweep))
+    }
+
+    /// Frame with the overlay drawn on a copy of the background
+    pub fn frame<T: ShapeRenderer + Clone>(&self, background: &T) -> T {
+        let mut frame = background.clone();
+        for rect in self.overlay() {
+            frame.draw(rect);
+        }
+        frame
+    }
+}
+
+impl<G: Plot> Graph for Playhead<G> {
+    fn size(&self) -> &Dimension {
+        self.graph.size()
+    }
+
+    fn base(&self) -> &Block {
+        self.graph.base()
+    }
+
+    fn margin(&self) -> &Dimension {
+        self.graph.margin()
+    }
+
+    fn padding(&self) -> &Block {
+        self.graph.padding()
+    }
+
+    fn draw<T>(&self, renderer: &mut T) -> Vec<u8>
+    where
+        T: ShapeRenderer,
+    {
+        for rect in self.renderables() {
+            renderer.draw(rect);
+        }
+
+        renderer.get_buffer().to_vec()
+    }
+
+    fn renderables(&self) -> Vec<Renderable> {
+        let mut renderables = self.renderables.clone();
+        renderables.append(&mut self.overlay());
+        renderables
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{ppm, Roll};
+    use rsound_output::{Buffer, OutputRenderer};
+
+    #[test]
+    fn cursor_at_time() {
+        let blocks = [Block(4.0, 1.0), Block(4.0, 3.0)];
+        let playhead = Playhead::new(Roll::new(&blocks))
+            .with_tint(0xBADA55)
+            .with_time(6.0);
+        let overlay = playhead.overlay();
+
+        assert_eq!(overlay.len(), 2);
+        match (&overlay[0], &overlay[1]) {
+            (Renderable::Rect(_, played, _), Renderable::Rect(cursor, _, _)) => {
+                assert_eq!(played.w, 6.0 * 20.0);
+                assert_eq!(cursor.x, 40.0 + 6.0 * 20.0 - 1.0);
+            }
+            _ => panic!("expected tint and cursor"),
+        }
+        let past = playhead.with_time(100.0);
+        match past.overlay().last() {
+            Some(Renderable::Rect(cursor, _, _)) => assert_eq!(cursor.x, 40.0 + 160.0 - 1.0),
+            _ => panic!("expected cursor"),
+        }
+    }
+
+    #[test]
+    fn animated_svg() {
+        let blocks = [Block(4.0, 1.0), Block(4.0, 3.0)];
+        let playhead = Playhead::new(Roll::new(&blocks))
+            .with_color(0xFF0000)
+            .with_highlight(0x00FF00);
+        let renderer = playhead.animate(8.0);
+        let body = String::from_utf8_lossy(renderer.get_buffer()).to_string();
+        let footer = String::from_utf8(renderer.get_footer().unwrap()).unwrap();
+
+        assert!(body.contains(
+            "values='rgb(0, 255, 0);rgb(186, 218, 85)' keyTimes='0;0.5' calcMode='discrete'"
+        ));
+        assert!(body.contains(
+            "values='rgb(186, 218, 85);rgb(0, 255, 0)' keyTimes='0;0.5' calcMode='discrete'"
+        ));
+        assert_eq!(
+            body.matches("attributeName='fill'").count(),
+            2,
+            "notes alone light up"
+        );
+        assert!(footer.contains(
+            "<animate attributeName='x' from='39' to='199' dur='8s' repeatCount='indefinite' />"
+        ));
+        assert!(footer.ends_with("</rect>\n</svg>"));
+        assert!(!footer.contains("'width'"), "no tint");
+    }
+
+    #[test]
+    fn frames_match_full_draw() {
+        let blocks = [Block(4.0, 1.0), Block(4.0, 3.0), Block(4.0, 2.0)];
+        let mut playhead = Playhead::new(Roll::new(&blocks)).with_tint(0xBADA55);
+        let background = playhead.background(ppm::Renderer::new(playhead.size()));
+        for time in [0.0, 5.5, 12.0] {
+            playhead.seek(time);
+            let mut full = ppm::Renderer::new(playhead.size());
+            playhead.draw(&mut full);
+
+            assert_eq!(playhead.frame(&background).get_buffer(), full.get_buffer());
+        }
+    }
+}
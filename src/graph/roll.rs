@@ -1,46 +1,235 @@
-use super::Graph;
-use crate::{Block, Dimension, Point, Renderable, ShapeRenderer, Style};
+use super::{label, left_tick, scaled, ticks, Graph, Plot, Scalable, Viewport, CHAR_HEIGHT};
+use crate::colormap::ColorScale;
+use crate::scale::Scale;
+use crate::{Block, Dimension, Meta, Point, Renderable, ShapeRenderer, Style};
 
 pub struct Roll<'a> {
     size: Dimension,
+    /// Scale positions of the lowest and highest row
     minimum: f64,
+    maximum: f64,
     base: Block,
     blocks: &'a [Block],
+    velocities: Option<&'a [f64]>,
+    colors: ColorScale,
+    legend: bool,
+    y_scale: Option<Scale>,
+    viewport: Option<Viewport>,
+    /// Index of the first block in the viewport and the blocks clipped to it
+    first: usize,
+    visible: Option<Vec<Block>>,
 }
 
 impl<'a> Roll<'a> {
     pub fn new(blocks: &'a [Block]) -> Self {
-        let base = Block(20.0, 20.0);
-        let width = blocks
-            .iter()
-            .fold(0.0, |total, block| total + block.0 * base.0);
-        let minimum = blocks
-            .iter()
-            .filter_map(|x| if x.1 > 0.0 { Some(x.1) } else { None })
-            .reduce(f64::min)
-            .expect("there has to be minimum");
-        let maximum = blocks
-            .iter()
-            .map(|x| x.1)
-            .reduce(f64::max)
-            .expect("there has to be maximum");
-        let height = ((maximum - minimum) + 1.0) * base.1;
         let mut roll = Self {
-            size: Dimension {
-                w: width,
-                h: height,
-            },
-            minimum,
-            base,
+            size: Dimension { w: 0.0, h: 0.0 },
+            minimum: 0.0,
+            maximum: 0.0,
+            base: Block(20.0, 20.0),
             blocks,
+            velocities: None,
+            colors: ColorScale::sequential(0x304050, 0xBADA55),
+            legend: false,
+            y_scale: None,
+            viewport: None,
+            first: 0,
+            visible: None,
         };
-        let &Block(dw, dh) = roll.padding();
-        let &Dimension { w: mw, h: mh } = roll.margin();
-        roll.size.w += (roll.base.0 * dw * 2.0) + mw * 2.0;
-        roll.size.h += (roll.base.1 * dh * 2.0) + mh * 2.0;
-
+        roll.resize();
         roll
     }
+
+    /// Blocks drawn, all of them unless there is a viewport
+    fn blocks(&self) -> &[Block] {
+        self.visible.as_deref().unwrap_or(self.blocks)
+    }
+
+    /// Scale positions of the lowest and highest note, falling back to the
+    /// whole piece when the viewport only holds rests
+    fn pitch_range(&self) -> (f64, f64) {
+        let notes = |blocks: &[Block]| {
+            blocks
+                .iter()
+                .filter(|x| x.1 > 0.0)
+                .map(|x| self.y(x.1))
+                .filter(|x| x.is_finite())
+                .map(|x| (x, x))
+                .reduce(|(lo, hi), (v, _)| (lo.min(v), hi.max(v)))
+        };
+        notes(self.blocks())
+            .or_else(|| notes(self.blocks))
+            .expect("there has to be minimum")
+    }
+
+    fn resize(&mut self) {
+        let width = self
+            .blocks()
+            .iter()
+            .fold(0.0, |total, block| total + block.0 * self.base.0);
+        (self.minimum, self.maximum) = match self.viewport.and_then(|x| x.values()) {
+            Some((lo, hi)) => (self.y(lo).floor(), self.y(hi).floor()),
+            None => self.pitch_range(),
+        };
+        self.size = Dimension {
+            w: width,
+            h: ((self.maximum - self.minimum) + 1.0) * self.base.1,
+        };
+        let &Block(dw, dh) = self.padding();
+        let &Dimension { w: mw, h: mh } = self.margin();
+        self.size.w += (self.base.0 * dw * 2.0) + mw * 2.0;
+        self.size.h += (self.base.1 * dh * 2.0) + mh * 2.0;
+    }
+
+    /// Scale position of a pitch
+    fn y(&self, value: f64) -> f64 {
+        scaled(self.y_scale.as_ref(), value)
+    }
+
+    /// Maps pitches onto the vertical axis, labelled with ticks.
+    /// One row spans one unit of scale position.
+    pub fn with_y_scale(mut self, scale: Scale) -> Self {
+        self.y_scale = Some(scale);
+        self.resize();
+        self
+    }
+
+    /// Per-block velocity, used to pick each note's fill from the color scale
+    pub fn with_velocity(mut self, velocities: &'a [f64]) -> Self {
+        self.velocities = Some(velocities);
+        self
+    }
+
+    /// Velocities map onto the scale domain, or their own range if it has none
+    pub fn with_colors(mut self, colors: ColorScale) -> Self {
+        self.colors = colors;
+        self
+    }
+
+    /// Draws velocity color scale with its range in the bottom margin
+    pub fn with_legend(mut self) -> Self {
+        self.legend = true;
+        self
+    }
+
+    /// Draws only the notes within the window, its values picking the rows shown.
+    /// A window past the last block leaves the plot empty.
+    pub fn with_viewport(mut self, viewport: Viewport) -> Self {
+        let (first, visible) = viewport.clip(self.blocks);
+        self.viewport = Some(viewport);
+        self.first = first;
+        self.visible = Some(visible);
+        self.resize();
+        self
+    }
+
+    /// Velocity range over the notes shown, ignoring rests
+    fn velocity_range(&self) -> Option<(f64, f64)> {
+        let velocities = self.velocities?;
+        self.blocks()
+            .iter()
+            .zip(velocities.iter().skip(self.first))
+            .filter(|(block, _)| self.shows(block))
+            .map(|(_, &v)| (v, v))
+            .reduce(|(lo, hi), (v, _)| (lo.min(v), hi.max(v)))
+    }
+
+    /// Whether the block is a note within the rows drawn
+    fn shows(&self, block: &Block) -> bool {
+        let row = self.y(block.1);
+        block.1 != 0.0 && row >= self.minimum && row <= self.maximum
+    }
+
+    /// Ticks and labels of the vertical axis at the middle of rows, if it is scaled
+    fn axis(&self) -> Vec<Renderable> {
+        let scale = match &self.y_scale {
+            Some(scale) => scale,
+            None => return Vec::new(),
+        };
+        let &Block(dw, dh) = self.padding();
+        let &Dimension { w: mw, h: mh } = self.margin();
+        let (left, bottom) = (
+            (self.base.0 * dw) + mw,
+            self.size.h - ((self.base.1 * dh) + mh),
+        );
+        ticks(
+            scale,
+            (self.minimum, self.maximum),
+            bottom - (self.base.1 * dh) - mh,
+            CHAR_HEIGHT * 2.0,
+        )
+        .into_iter()
+        .flat_map(|(position, value)| {
+            let y = bottom - (position - self.minimum + 0.5) * self.base.1;
+            left_tick(left, y, value)
+        })
+        .collect()
+    }
+
+    /// Color scale fitted to the velocities, if there are any
+    fn velocity_scale(&self) -> Option<ColorScale> {
+        self.velocity_range()
+            .map(|range| self.colors.clone().fit(range))
+    }
+
+    fn legend(&self) -> Vec<Renderable> {
+        let scale = match self.velocity_scale() {
+            Some(scale) => scale,
+            None => return Vec::new(),
+        };
+        let (lo, hi) = scale.domain();
+        let &Dimension { w: mw, h: mh } = self.margin();
+        let height = self.size.h;
+        let steps = 10;
+        let swatch = Dimension {
+            w: self.base.0 / 2.0,
+            h: mh / 4.0,
+        };
+        let top = height - mh + swatch.h;
+        let text = Style::color(0x909090);
+        let mut legend = vec![Renderable::Text(Point { x: mw, y: top }, label(lo), text)];
+        let left = mw + self.base.0 * 2.0;
+        for step in 0..steps {
+            let color = scale.at(step as f64 / (steps - 1) as f64);
+            legend.push(Renderable::Rect(
+                Point {
+                    x: left + step as f64 * swatch.w,
+                    y: top,
+                },
+                swatch,
+                Style::from(color),
+            ));
+        }
+        legend.push(Renderable::Text(
+            Point {
+                x: left + steps as f64 * swatch.w + self.base.0 / 2.0,
+                y: top,
+            },
+            label(hi),
+            text,
+        ));
+        legend
+    }
+}
+
+impl<'a> Scalable for Roll<'a> {
+    fn with_base(mut self, base: Block) -> Self {
+        self.base = base;
+        self.resize();
+        self
+    }
+}
+
+impl<'a> Plot for Roll<'a> {
+    /// Middle of the row of a pitch
+    fn position(&self, time: f64, value: f64) -> Point {
+        let (area, size) = self.area();
+        let start = self.viewport.map_or(0.0, |x| x.start());
+        Point {
+            x: area.x + (time - start) * self.base.0,
+            y: area.y + size.h - (self.y(value) - self.minimum + 0.5) * self.base.1,
+        }
+    }
 }
 
 impl<'a> Graph for Roll<'a> {
@@ -66,7 +255,7 @@ impl<'a> Graph for Roll<'a> {
     fn renderables(&self) -> Vec<Renderable> {
         let &Dimension {
             h: height,
-            w: _width,
+            w: width,
         } = self.size();
         let &Block(dw, dh) = self.padding();
         let &Dimension { w: mw, h: mh } = self.margin();
@@ -77,17 +266,19 @@ impl<'a> Graph for Roll<'a> {
         let style = Style::color(0xDEAD00)
             .with_border(2.0)
             .with_background(0xBADA55);
+        let scale = self.velocity_scale();
         let mut renderables = self.grid();
-        renderables.append(
-            &mut self
-                .blocks
+        renderables.push(Renderable::Group(
+            self.blocks()
                 .iter()
-                .filter_map(|block| {
-                    if block.1 == 0.0 {
+                .enumerate()
+                .filter_map(|(idx, block)| {
+                    if !self.shows(block) {
                         prev.x += block.0 * self.base.0;
                         return None;
                     }
-                    let mut delta_y = block.1 * self.base.1;
+                    let velocity = self.velocities.and_then(|v| v.get(self.first + idx));
+                    let mut delta_y = self.y(block.1) * self.base.1;
                     delta_y -= self.minimum * self.base.1;
                     delta_y = (height - prev.y * 2.0 - self.base.1) - delta_y;
                     let rect = Renderable::Rect(
@@ -99,14 +290,36 @@ impl<'a> Graph for Roll<'a> {
                             w: block.0 * self.base.0,
                             h: self.base.1,
                         },
-                        style,
+                        match (&scale, velocity) {
+                            (Some(scale), Some(&velocity)) => {
+                                style.with_fill(scale.color(velocity))
+                            }
+                            _ => style,
+                        },
                     );
+                    let mut meta = Meta::class("note roll")
+                        .with_data("duration", block.0)
+                        .with_data("intensity", block.1);
+                    let mut title = format!("duration {}, intensity {}", block.0, block.1);
+                    if let Some(velocity) = velocity {
+                        meta = meta.with_data("velocity", velocity);
+                        title += &format!(", velocity {}", velocity);
+                    }
 
                     prev.x += block.0 * self.base.0;
-                    Some(rect)
+                    Some(rect.with_meta(meta.with_title(&title)))
                 })
-                .collect::<Vec<Renderable>>(),
-        );
+                .collect(),
+            Meta::class("data"),
+        ));
+        if let Some(viewport) = &self.viewport {
+            let (left, top) = ((self.base.0 * dw) + mw, (self.base.1 * dh) + mh);
+            renderables.append(&mut viewport.labels(self.blocks(), left, width - left, top));
+        }
+        renderables.append(&mut self.axis());
+        if self.legend {
+            renderables.append(&mut self.legend());
+        }
         renderables
     }
 }
@@ -114,7 +327,22 @@ impl<'a> Graph for Roll<'a> {
 #[cfg(test)]
 mod tests {
     use super::*;
-    use crate::ppm;
+    use crate::{ppm, svg, Color};
+    use rsound_output::Buffer;
+
+    /// Note shapes without their metadata
+    fn notes(graph: &Roll) -> Vec<Renderable> {
+        match &graph.renderables()[graph.grid().len()] {
+            Renderable::Group(notes, _) => notes
+                .iter()
+                .map(|note| match note {
+                    Renderable::Tagged(shape, _) => *shape.clone(),
+                    _ => panic!("expected tagged note"),
+                })
+                .collect(),
+            _ => panic!("expected group of notes"),
+        }
+    }
 
     #[test]
     fn graph_dimensions_from_blocks() {
@@ -151,6 +379,99 @@ mod tests {
         assert_eq!(buf.len(), (graph.size.w * graph.size.h) as usize * 3);
     }
 
+    #[test]
+    fn velocity_colors_notes() {
+        let blocks = [Block(4.0, 1.0), Block(4.0, 0.0), Block(4.0, 3.0)];
+        let (soft, loud) = (Color::from(0x000000), Color::from(0xFFFFFF));
+        let graph = Roll::new(&blocks)
+            .with_velocity(&[0.0, 127.0, 100.0])
+            .with_colors(ColorScale::new(&[0x000000, 0xFFFFFF]));
+        let rects = notes(&graph);
+
+        assert_eq!(rects.len(), 2);
+        if let Renderable::Rect(_, _, style) = &rects[0] {
+            assert_eq!(style.get_color(), &soft, "softest note uses scale start");
+        } else {
+            panic!("expected note rect");
+        }
+        if let Renderable::Rect(_, _, style) = &rects[1] {
+            assert_eq!(style.get_color(), &loud, "loudest note uses scale end");
+        } else {
+            panic!("expected note rect");
+        }
+    }
+
+    #[test]
+    fn notes_carry_metadata() {
+        let blocks = [Block(4.0, 1.0), Block(2.0, 3.0)];
+        let graph = Roll::new(&blocks).with_velocity(&[64.0, 100.0]);
+        let mut renderer = svg::Renderer::new(graph.size());
+        graph.draw(&mut renderer);
+        let svg = String::from_utf8(renderer.get_buffer().to_vec()).unwrap();
+
+        assert!(svg.starts_with("<g class='grid'>\n<rect x='0' y='0'"));
+        assert!(svg.contains("</g>\n<g class='data'>\n<rect class='note roll'"));
+        assert!(svg.contains(
+            "<rect class='note roll' data-duration='2' data-intensity='3' data-velocity='100' \
+             x='120' y='40' width='40' height='20' fill='rgb(186, 218, 85)' \
+             stroke='rgb(222, 173, 0)' stroke-width='2'>\
+             <title>duration 2, intensity 3, velocity 100</title></rect>\n"
+        ));
+        assert!(svg.ends_with("</rect>\n</g>\n"));
+    }
+
+    #[test]
+    fn viewport_clips_notes() {
+        let blocks = [
+            Block(4.0, 1.0),
+            Block(4.0, 3.0),
+            Block(4.0, 1.0),
+            Block(4.0, 2.0),
+        ];
+        let graph = Roll::new(&blocks)
+            .with_velocity(&[10.0, 20.0, 30.0, 40.0])
+            .with_viewport(Viewport::new(6.0, 14.0).with_values(1.0, 2.0));
+        let rects = graph.renderables();
+        let idx = graph.grid().len();
+
+        assert_eq!(graph.size.w, 8.0 * 20.0 + 80.0, "window width");
+        assert_eq!(graph.size.h, 2.0 * 20.0 + 80.0, "two rows");
+        assert_eq!(
+            graph.velocity_range(),
+            Some((30.0, 40.0)),
+            "velocities follow"
+        );
+        assert_eq!(rects.len(), idx + 1 + 2, "notes and two labels");
+        assert_eq!(notes(&graph).len(), 2, "third note out of range");
+        match &notes(&graph)[0] {
+            Renderable::Rect(pos, size, _) => {
+                assert_eq!(
+                    pos.x,
+                    40.0 + 2.0 * 20.0,
+                    "after the note outside the value range"
+                );
+                assert_eq!(size.w, 4.0 * 20.0);
+            }
+            _ => panic!("expected note rect"),
+        }
+        match (&rects[idx + 1], &rects[idx + 2]) {
+            (Renderable::Text(_, start, _), Renderable::Text(_, end, _)) => {
+                assert_eq!((start.as_str(), end.as_str()), ("6", "14"), "absolute time");
+            }
+            _ => panic!("expected time labels"),
+        }
+    }
+
+    #[test]
+    fn viewport_past_the_end() {
+        let blocks = [Block(4.0, 1.0), Block(4.0, 3.0)];
+        let graph = Roll::new(&blocks).with_viewport(Viewport::new(10.0, 14.0));
+
+        assert_eq!(graph.size.w, 80.0, "nothing to draw");
+        assert_eq!(graph.size.h, 3.0 * 20.0 + 80.0, "rows of the whole piece");
+        assert!(notes(&graph).is_empty());
+    }
+
     #[test]
     fn graph_rects() {
         let graph = Roll::new(&[
@@ -164,7 +485,8 @@ mod tests {
 
         let idx = grid.len();
 
-        assert_eq!(rects.len(), idx + 4);
+        assert_eq!(rects.len(), idx + 1);
+        assert_eq!(notes(&graph).len(), 4);
 
         /*
          * TODO: fix these
@@ -217,4 +539,17 @@ mod tests {
         assert_eq!(size.h, 5.0, "fourth rect should be at h=5");
         */
     }
+
+    #[test]
+    fn octaves_on_log_scale() {
+        let blocks = [Block(1.0, 110.0), Block(1.0, 220.0), Block(1.0, 440.0)];
+        let roll = Roll::new(&blocks).with_y_scale(Scale::Log2);
+
+        assert!(
+            (roll.size.h - (3.0 * 20.0 + 80.0)).abs() < 1e-9,
+            "a row per octave"
+        );
+        assert!((roll.position(1.0, 220.0).y - (40.0 + 60.0 - 30.0)).abs() < 1e-9);
+        assert_eq!(notes(&roll).len(), 3);
+    }
 }
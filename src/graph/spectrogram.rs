@@ -0,0 +1,395 @@
+use super::waveform::Timeline;
+use super::{label, left_tick, ticks, Graph, Plot, Samples, Scalable, Viewport, CHAR_HEIGHT};
+use crate::colormap::ColorScale;
+use crate::fft::{spectrum, Window};
+use crate::scale::Scale;
+use crate::{Block, Dimension, Meta, Point, Renderable, ShapeRenderer, Style};
+
+pub struct Spectrogram<'a> {
+    size: Dimension,
+    base: Block,
+    samples: Samples<'a>,
+    sample_rate: u32,
+    channels: usize,
+    tempo: f64,
+    window_size: usize,
+    hop: usize,
+    window: Window,
+    frequencies: Scale,
+    floor: f64,
+    colors: ColorScale,
+    viewport: Option<Viewport>,
+}
+
+impl<'a> Spectrogram<'a> {
+    pub fn new<T>(samples: T, sample_rate: u32) -> Self
+    where
+        T: Into<Samples<'a>>,
+    {
+        assert!(sample_rate > 0, "there has to be a sample rate");
+        let mut spectrogram = Self {
+            size: Dimension { w: 0.0, h: 0.0 },
+            base: Block(20.0, 20.0),
+            samples: samples.into(),
+            sample_rate,
+            channels: 1,
+            tempo: 1.0,
+            window_size: 1024,
+            hop: 256,
+            window: Window::Hann,
+            frequencies: Scale::Linear,
+            floor: -90.0,
+            colors: ColorScale::magma(),
+            viewport: None,
+        };
+        spectrogram.resize();
+        spectrogram
+    }
+
+    /// Interleaved channels, mixed down before analysis
+    pub fn with_channels(mut self, channels: usize) -> Self {
+        self.channels = channels.max(1);
+        self.resize();
+        self
+    }
+
+    /// Block duration units per second, as with `Waveform`
+    pub fn with_tempo(mut self, units_per_second: f64) -> Self {
+        self.tempo = units_per_second;
+        self.resize();
+        self
+    }
+
+    /// Samples per analysis frame, rounded up to a power of two
+    pub fn with_window_size(mut self, size: usize) -> Self {
+        self.window_size = size.max(2).next_power_of_two();
+        self
+    }
+
+    /// Samples between the starts of consecutive frames
+    pub fn with_hop(mut self, hop: usize) -> Self {
+        self.hop = hop.max(1);
+        self
+    }
+
+    pub fn with_window(mut self, window: Window) -> Self {
+        self.window = window;
+        self
+    }
+
+    /// Maps frequencies in hertz onto the vertical axis, starting from the
+    /// lowest bin above zero where the scale is undefined at zero
+    pub fn with_frequency_scale(mut self, frequencies: Scale) -> Self {
+        self.frequencies = frequencies;
+        self
+    }
+
+    /// Quietest level shown, in dB relative to full scale
+    pub fn with_floor(mut self, floor: f64) -> Self {
+        self.floor = floor.min(-1.0);
+        self
+    }
+
+    /// Levels map onto the scale domain, or `floor..0` dB if it has none
+    pub fn with_colors(mut self, colors: ColorScale) -> Self {
+        self.colors = colors;
+        self
+    }
+
+    /// Draws only the analysis frames starting within the window of time, its
+    /// values ignored. A window past the last sample leaves the plot empty.
+    pub fn with_viewport(mut self, viewport: Viewport) -> Self {
+        self.viewport = Some(viewport);
+        self.resize();
+        self
+    }
+
+    fn timeline(&self) -> Timeline {
+        Timeline::new(
+            self.samples.len(),
+            self.channels,
+            self.sample_rate,
+            self.tempo,
+            self.viewport,
+        )
+    }
+
+    /// Length in block duration units
+    pub fn duration(&self) -> f64 {
+        self.timeline().duration()
+    }
+
+    fn lane(&self) -> f64 {
+        self.base.1 * 8.0
+    }
+
+    fn resize(&mut self) {
+        self.size = Dimension {
+            w: self.timeline().width(self.base.0),
+            h: self.lane(),
+        };
+        let &Block(dw, dh) = self.padding();
+        let &Dimension { w: mw, h: mh } = self.margin();
+        self.size.w += (self.base.0 * dw * 2.0) + mw * 2.0;
+        self.size.h += (self.base.1 * dh * 2.0) + mh * 2.0;
+    }
+
+    fn mono(&self, frame: usize) -> f64 {
+        let start = frame * self.channels;
+        (start..start + self.channels)
+            .map(|idx| self.samples.get(idx) as f64)
+            .sum::<f64>()
+            / self.channels as f64
+    }
+
+    /// Level in dB of each analysis frame, per frequency bin
+    fn levels(&self) -> Vec<Vec<f64>> {
+        let timeline = self.timeline();
+        let frames = timeline.frames();
+        let window = self.window.coefficients(self.window_size);
+        let (mut start, end) = timeline.window();
+        let mut levels = Vec::new();
+        while start < end {
+            let frame: Vec<f64> = (start..(start + self.window_size).min(frames))
+                .map(|idx| self.mono(idx))
+                .collect();
+            levels.push(
+                spectrum(&frame, &window)
+                    .into_iter()
+                    .map(|x| (20.0 * x.max(1e-12).log10()).clamp(self.floor, 0.0))
+                    .collect(),
+            );
+            start += self.hop;
+        }
+        levels
+    }
+
+    /// Scale positions of the lowest and highest frequency shown
+    fn span(&self) -> (f64, f64) {
+        let nyquist = self.sample_rate as f64 / 2.0;
+        let resolution = self.sample_rate as f64 / self.window_size as f64;
+        let lo = match self.frequencies.forward(0.0) {
+            lo if lo.is_finite() => lo,
+            _ => self.frequencies.forward(resolution),
+        };
+        (lo, self.frequencies.forward(nyquist))
+    }
+
+    /// Bin range covered by each band, lowest band first
+    fn bands(&self, count: usize) -> Vec<(usize, usize)> {
+        let resolution = self.sample_rate as f64 / self.window_size as f64;
+        let (lo, hi) = self.span();
+        (0..count)
+            .map(|band| {
+                let hz = |t: f64| self.frequencies.inverse(lo + (hi - lo) * t);
+                let start = (hz(band as f64 / count as f64) / resolution).round() as usize;
+                let end = (hz((band + 1) as f64 / count as f64) / resolution).round() as usize;
+                (start, end.max(start + 1))
+            })
+            .collect()
+    }
+}
+
+impl<'a> Scalable for Spectrogram<'a> {
+    fn with_base(mut self, base: Block) -> Self {
+        self.base = base;
+        self.resize();
+        self
+    }
+}
+
+impl<'a> Plot for Spectrogram<'a> {
+    /// Frequency in hertz along the vertical axis
+    fn position(&self, time: f64, value: f64) -> Point {
+        let (area, size) = self.area();
+        let start = self.timeline().start();
+        let (lo, hi) = self.span();
+        Point {
+            x: area.x + (time - start) * self.base.0,
+            y: area.y + size.h - (self.frequencies.forward(value) - lo) / (hi - lo) * size.h,
+        }
+    }
+}
+
+impl<'a> Graph for Spectrogram<'a> {
+    fn size(&self) -> &Dimension {
+        &self.size
+    }
+
+    fn base(&self) -> &Block {
+        &self.base
+    }
+
+    fn draw<T>(&self, renderer: &mut T) -> Vec<u8>
+    where
+        T: ShapeRenderer,
+    {
+        for rect in self.renderables() {
+            renderer.draw(rect);
+        }
+
+        renderer.get_buffer().to_vec()
+    }
+
+    fn renderables(&self) -> Vec<Renderable> {
+        let &Block(dw, dh) = self.padding();
+        let &Dimension { w: mw, h: mh } = self.margin();
+        let left = (self.base.0 * dw) + mw;
+        let bottom = (self.base.1 * dh) + mh + self.lane();
+        let scale = self.colors.clone().fit((self.floor, 0.0));
+        let column = self.timeline().time(self.hop) * self.base.0;
+        let count = (self.lane() / 2.0) as usize;
+        let band = self.lane() / count as f64;
+        let bands = self.bands(count);
+        let mut renderables = self.grid();
+        let mut cells = Vec::new();
+        for (x, levels) in self.levels().iter().enumerate() {
+            for (y, &(start, end)) in bands.iter().enumerate() {
+                let level = levels[start.min(levels.len() - 1)..end.min(levels.len())]
+                    .iter()
+                    .cloned()
+                    .fold(self.floor, f64::max);
+                let meta = Meta::class("cell")
+                    .with_data("frame", x)
+                    .with_data("band", y)
+                    .with_data("value", label(level));
+                cells.push(
+                    Renderable::Rect(
+                        Point {
+                            x: left + x as f64 * column,
+                            y: bottom - (y + 1) as f64 * band,
+                        },
+                        Dimension {
+                            w: column.max(1.0),
+                            h: band,
+                        },
+                        Style::from(scale.color(level)),
+                    )
+                    .with_meta(meta),
+                );
+            }
+        }
+        renderables.push(Renderable::Group(cells, Meta::class("data")));
+        let (lo, hi) = self.span();
+        for (position, hz) in ticks(&self.frequencies, (lo, hi), self.lane(), CHAR_HEIGHT * 2.0) {
+            let y = bottom - (position - lo) / (hi - lo) * self.lane();
+            renderables.append(&mut left_tick(left, y, hz));
+        }
+        renderables
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::graph::data;
+
+    use std::f32::consts::PI;
+
+    #[test]
+    #[should_panic(expected = "sample rate")]
+    fn zero_sample_rate() {
+        Spectrogram::new(&[0.0f32; 4][..], 0);
+    }
+
+    fn sine(hz: f32, rate: u32, len: usize) -> Vec<f32> {
+        (0..len)
+            .map(|n| (2.0 * PI * hz * n as f32 / rate as f32).sin())
+            .collect()
+    }
+
+    #[test]
+    fn frames_and_size() {
+        let samples = sine(440.0, 8000, 8000);
+        let spectrogram = Spectrogram::new(&samples[..], 8000)
+            .with_window_size(500)
+            .with_hop(1000);
+
+        assert_eq!(spectrogram.window_size, 512);
+        assert_eq!(spectrogram.levels().len(), 8);
+        assert_eq!(spectrogram.size.w, 20.0 + 80.0);
+        assert_eq!(spectrogram.size.h, 160.0 + 80.0);
+        assert_eq!(
+            data(spectrogram.renderables()).len(),
+            8 * 80,
+            "a cell per frame and band"
+        );
+    }
+
+    #[test]
+    fn sine_is_loudest_at_its_frequency() {
+        let samples = sine(1000.0, 8000, 1024);
+        let spectrogram = Spectrogram::new(&samples[..], 8000).with_hop(1024);
+        let levels = &spectrogram.levels()[0];
+        let peak = levels
+            .iter()
+            .enumerate()
+            .max_by(|a, b| a.1.total_cmp(b.1))
+            .map(|(bin, _)| bin)
+            .unwrap();
+
+        assert_eq!(peak, 128, "1kHz at 7.8Hz per bin");
+        assert!(levels[peak] > -7.0, "near full scale, got {}", levels[peak]);
+    }
+
+    #[test]
+    fn bands_cover_spectrum() {
+        let samples = [0.0f32; 16];
+        for scale in [Scale::Linear, Scale::Log10, Scale::Log2, Scale::Mel] {
+            let spectrogram =
+                Spectrogram::new(&samples[..], 8000).with_frequency_scale(scale.clone());
+            let bands = spectrogram.bands(80);
+
+            assert_eq!(bands.len(), 80);
+            assert!(bands.windows(2).all(|w| w[0].0 <= w[1].0), "{:?}", scale);
+            assert_eq!(bands[79].1, 512, "{:?} reaches nyquist", scale);
+        }
+    }
+
+    #[test]
+    fn frequency_labels() {
+        let samples = [0.0f32; 16];
+        let spectrogram = Spectrogram::new(&samples[..], 8000)
+            .with_window_size(16)
+            .with_frequency_scale(Scale::Log10);
+        let labels: Vec<String> = spectrogram
+            .renderables()
+            .into_iter()
+            .filter_map(|x| match x {
+                Renderable::Text(_, text, _) => Some(text),
+                _ => None,
+            })
+            .collect();
+
+        assert_eq!(spectrogram.span(), (500f64.log10(), 4000f64.log10()));
+        assert_eq!(labels.first().map(String::as_str), Some("501.19"));
+        assert!(labels.contains(&"1000".to_string()));
+        assert_eq!(labels.last().map(String::as_str), Some("3981.07"));
+    }
+
+    #[test]
+    fn viewport_window() {
+        let samples = sine(440.0, 8000, 8000);
+        let spectrogram = Spectrogram::new(&samples[..], 8000)
+            .with_hop(1000)
+            .with_viewport(Viewport::new(0.25, 0.75));
+
+        assert_eq!(
+            spectrogram.levels().len(),
+            4,
+            "frames starting in the window"
+        );
+        assert_eq!(spectrogram.size.w, 10.0 + 80.0);
+    }
+
+    #[test]
+    fn plotted_by_frequency() {
+        let samples = [0.0f32; 8000];
+        let spectrogram = Spectrogram::new(&samples[..], 8000).with_frequency_scale(Scale::Mel);
+        let (area, size) = spectrogram.area();
+
+        assert_eq!(spectrogram.position(0.5, 0.0).x, 40.0 + 10.0);
+        assert_eq!(spectrogram.position(0.5, 0.0).y, area.y + size.h);
+        assert!((spectrogram.position(0.5, 4000.0).y - area.y).abs() < 1e-9);
+    }
+}
@@ -0,0 +1,205 @@
+use super::{Graph, Viewport};
+use crate::{Block, Dimension, Point, Renderable, ShapeRenderer, Style};
+
+/// Long sequence wrapped into rows of equal length, like the systems of a score
+pub struct Systems<G> {
+    size: Dimension,
+    rows: Vec<G>,
+    per_page: usize,
+}
+
+impl<G: Graph> Systems<G> {
+    /// Rows of `length` duration units, bars per row times the duration of a bar,
+    /// until `duration`. Each row is drawn by `row` for its window; giving every
+    /// window the same values keeps the rows alike in height.
+    pub fn new<F>(duration: f64, length: f64, row: F) -> Self
+    where
+        F: Fn(Viewport) -> G,
+    {
+        assert!(length > 0.0, "rows have to have a length");
+        let count = (duration / length).ceil().max(1.0) as usize;
+        let rows: Vec<G> = (0..count)
+            .map(|idx| {
+                let start = idx as f64 * length;
+                row(Viewport::new(
+                    start,
+                    (start + length).min(duration.max(length)),
+                ))
+            })
+            .collect();
+        let mut systems = Self {
+            size: Dimension { w: 0.0, h: 0.0 },
+            per_page: rows.len(),
+            rows,
+        };
+        systems.resize();
+        systems
+    }
+
+    /// Splits rows into pages, every page the size of the tallest
+    pub fn with_rows_per_page(mut self, rows: usize) -> Self {
+        self.per_page = rows.max(1);
+        self.resize();
+        self
+    }
+
+    pub fn rows(&self) -> &[G] {
+        &self.rows
+    }
+
+    pub fn pages(&self) -> usize {
+        self.rows.len().div_ceil(self.per_page)
+    }
+
+    /// Rows of one page, drawn on their own
+    pub fn page(&self, idx: usize) -> Page<'_, G> {
+        let from = (idx * self.per_page).min(self.rows.len());
+        let to = (from + self.per_page).min(self.rows.len());
+        Page {
+            size: self.size,
+            base: self.base(),
+            rows: &self.rows[from..to],
+        }
+    }
+
+    fn resize(&mut self) {
+        let width = self.rows.iter().map(|x| x.size().w).fold(0.0, f64::max);
+        let height = self
+            .rows
+            .chunks(self.per_page)
+            .map(|page| page.iter().map(|x| x.size().h).sum::<f64>())
+            .fold(0.0, f64::max);
+        self.size = Dimension {
+            w: width,
+            h: height,
+        };
+    }
+}
+
+/// Rows of a page stacked top to bottom
+fn stack<G: Graph>(size: Dimension, rows: &[G]) -> Vec<Renderable> {
+    let mut renderables = vec![Renderable::Rect(
+        Point { x: 0.0, y: 0.0 },
+        size,
+        Style::color(0x060910),
+    )];
+    let mut offset = 0.0;
+    for row in rows {
+        renderables.extend(
+            row.renderables()
+                .into_iter()
+                .map(|x| x.translate(0.0, offset)),
+        );
+        offset += row.size().h;
+    }
+    renderables
+}
+
+impl<G: Graph> Graph for Systems<G> {
+    /// Size of a page, all of the rows unless split into pages
+    fn size(&self) -> &Dimension {
+        &self.size
+    }
+
+    fn base(&self) -> &Block {
+        self.rows[0].base()
+    }
+
+    /// Rows will take care of this
+    fn margin(&self) -> &Dimension {
+        &Dimension { w: 0.0, h: 0.0 }
+    }
+
+    fn draw<T>(&self, renderer: &mut T) -> Vec<u8>
+    where
+        T: ShapeRenderer,
+    {
+        for rect in self.renderables() {
+            renderer.draw(rect);
+        }
+
+        renderer.get_buffer().to_vec()
+    }
+
+    /// First page
+    fn renderables(&self) -> Vec<Renderable> {
+        self.page(0).renderables()
+    }
+}
+
+/// One page of systems, the same size as every other page
+pub struct Page<'a, G> {
+    size: Dimension,
+    base: &'a Block,
+    rows: &'a [G],
+}
+
+impl<'a, G: Graph> Graph for Page<'a, G> {
+    fn size(&self) -> &Dimension {
+        &self.size
+    }
+
+    fn base(&self) -> &Block {
+        self.base
+    }
+
+    /// Rows will take care of this
+    fn margin(&self) -> &Dimension {
+        &Dimension { w: 0.0, h: 0.0 }
+    }
+
+    fn draw<T>(&self, renderer: &mut T) -> Vec<u8>
+    where
+        T: ShapeRenderer,
+    {
+        for rect in self.renderables() {
+            renderer.draw(rect);
+        }
+
+        renderer.get_buffer().to_vec()
+    }
+
+    fn renderables(&self) -> Vec<Renderable> {
+        stack(self.size, self.rows)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{Roll, Track};
+
+    #[test]
+    fn wraps_rows_of_bars() {
+        let blocks: Vec<Block> = (0..10).map(|x| Block(4.0, (x % 3 + 1) as f64)).collect();
+        let systems = Systems::new(40.0, 16.0, |viewport| {
+            Roll::new(&blocks).with_viewport(viewport.with_values(1.0, 3.0))
+        });
+        let widths: Vec<f64> = systems.rows().iter().map(|x| x.size().w).collect();
+
+        assert_eq!(
+            widths,
+            vec![16.0 * 20.0 + 80.0, 16.0 * 20.0 + 80.0, 8.0 * 20.0 + 80.0]
+        );
+        assert_eq!(systems.size().w, 16.0 * 20.0 + 80.0, "widest row");
+        assert_eq!(systems.size().h, 3.0 * (3.0 * 20.0 + 80.0), "rows stacked");
+        assert_eq!(systems.pages(), 1);
+    }
+
+    #[test]
+    fn pages_share_size() {
+        let hits: Vec<Block> = (0..16).map(|x| Block(2.0, (x % 4) as f64)).collect();
+        let notes: Vec<Block> = (0..16).map(|x| Block(2.0, (60 + x % 5) as f64)).collect();
+        let systems = Systems::new(32.0, 8.0, |viewport| {
+            Track::new(&hits, &notes).with_viewport(viewport.with_values(60.0, 64.0))
+        })
+        .with_rows_per_page(3);
+        let (first, last) = (systems.page(0), systems.page(1));
+        let row = systems.rows()[0].size().h;
+
+        assert_eq!(systems.pages(), 2);
+        assert_eq!(first.size().h, 3.0 * row);
+        assert_eq!(last.size().h, first.size().h, "last page padded");
+        assert!(last.renderables().len() < first.renderables().len());
+    }
+}
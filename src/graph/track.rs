@@ -1,36 +1,137 @@
-use super::{Graph, Hits, Roll};
-use crate::{Block, Dimension, Renderable, ShapeRenderer};
+use super::{Graph, Hits, Plot, Roll, Scalable, Spectrogram, Viewport, Waveform};
+use crate::{Block, Dimension, Point, Renderable, ShapeRenderer};
 
 pub struct Track<'a> {
     size: Dimension,
     base: Block,
     hits: Hits<'a>,
     roll: Roll<'a>,
+    waveform: Option<Waveform<'a>>,
+    spectrogram: Option<Spectrogram<'a>>,
+    /// Window of time every lane is clipped to
+    viewport: Option<Viewport>,
 }
 
 impl<'a> Track<'a> {
     pub fn new(hsrc: &'a [Block], rsrc: &'a [Block]) -> Self {
-        let base = Block(20.0, 20.0);
-        let hits = Hits::new(hsrc);
-        let roll = Roll::new(rsrc);
-        let width = hits.size().w;
-        let height = hits.size().h + roll.size().h;
         let mut track = Self {
-            size: Dimension {
-                w: width,
-                h: height,
-            },
-            base,
-            hits,
-            roll,
+            size: Dimension { w: 0.0, h: 0.0 },
+            base: Block(20.0, 20.0),
+            hits: Hits::new(hsrc),
+            roll: Roll::new(rsrc),
+            waveform: None,
+            spectrogram: None,
+            viewport: None,
         };
-        let &Block(dw, dh) = track.padding();
-        let &Dimension { w: mw, h: mh } = track.margin();
-        track.size.w += (track.base.0 * dw * 2.0) + mw * 2.0;
-        track.size.h += (track.base.1 * dh * 2.0) + mh * 2.0;
-
+        track.resize();
         track
     }
+
+    /// Audio lane below the hits, sharing their time axis when tempo matches
+    pub fn with_waveform(mut self, waveform: Waveform<'a>) -> Self {
+        self.waveform = Some(match self.viewport {
+            Some(time) => waveform.with_viewport(time),
+            None => waveform,
+        });
+        self.resize();
+        self
+    }
+
+    /// Frequency lane at the bottom, sharing the time axis when tempo matches
+    pub fn with_spectrogram(mut self, spectrogram: Spectrogram<'a>) -> Self {
+        self.spectrogram = Some(match self.viewport {
+            Some(time) => spectrogram.with_viewport(time),
+            None => spectrogram,
+        });
+        self.resize();
+        self
+    }
+
+    /// Zooms every lane into the window, its values picking the rows of notes
+    /// shown. Audio lanes added later are clipped alike.
+    pub fn with_viewport(mut self, viewport: Viewport) -> Self {
+        let time = Viewport::new(viewport.start(), viewport.end());
+        self.hits = self.hits.with_viewport(time);
+        self.roll = self.roll.with_viewport(viewport);
+        self.waveform = self.waveform.map(|x| x.with_viewport(time));
+        self.spectrogram = self.spectrogram.map(|x| x.with_viewport(time));
+        self.viewport = Some(time);
+        self.resize();
+        self
+    }
+
+    /// Lanes stacked on top of each other
+    fn resize(&mut self) {
+        let mut lanes = vec![self.hits.size()];
+        lanes.extend(self.waveform.as_ref().map(|x| x.size()));
+        lanes.extend(self.spectrogram.as_ref().map(|x| x.size()));
+        self.size = Dimension {
+            w: lanes.iter().map(|x| x.w).fold(0.0, f64::max),
+            h: self.roll.size().h + lanes.iter().map(|x| x.h).sum::<f64>(),
+        };
+        let &Block(dw, dh) = self.padding();
+        let &Dimension { w: mw, h: mh } = self.margin();
+        self.size.w += (self.base.0 * dw * 2.0) + mw * 2.0;
+        self.size.h += (self.base.1 * dh * 2.0) + mh * 2.0;
+    }
+}
+
+impl<'a> Scalable for Track<'a> {
+    /// Every lane is scaled alike, keeping the time axis shared
+    fn with_base(mut self, base: Block) -> Self {
+        let Block(w, h) = base;
+        self.base = base;
+        self.hits = self.hits.with_base(Block(w, h));
+        self.roll = self.roll.with_base(Block(w, h));
+        self.waveform = self.waveform.map(|x| x.with_base(Block(w, h)));
+        self.spectrogram = self.spectrogram.map(|x| x.with_base(Block(w, h)));
+        self.resize();
+        self
+    }
+}
+
+impl<'a> Plot for Track<'a> {
+    /// Pitch on the notes at the top, the time axis being shared by every lane
+    fn position(&self, time: f64, value: f64) -> Point {
+        self.roll.position(time, value)
+    }
+
+    /// From the top of the notes down to the bottom of the last lane
+    fn area(&self) -> (Point, Dimension) {
+        let (top, size) = self.roll.area();
+        let below = match (&self.spectrogram, &self.waveform) {
+            (Some(spectrogram), _) => below(spectrogram),
+            (None, Some(waveform)) => below(waveform),
+            (None, None) => below(&self.hits),
+        };
+        (
+            top,
+            Dimension {
+                w: size.w,
+                h: self.size.h - below - top.y,
+            },
+        )
+    }
+
+    /// After the grid of the last lane, whose background covers the lanes
+    /// above it
+    fn data_start(&self) -> usize {
+        let mut lanes = vec![(self.hits.renderables().len(), self.hits.grid().len())];
+        if let Some(waveform) = &self.waveform {
+            lanes.push((waveform.renderables().len(), waveform.grid().len()));
+        }
+        if let Some(spectrogram) = &self.spectrogram {
+            lanes.push((spectrogram.renderables().len(), spectrogram.grid().len()));
+        }
+        let (_, grid) = lanes.pop().expect("there has to be a lane of hits");
+        self.roll.renderables().len() + lanes.iter().map(|x| x.0).sum::<usize>() + grid
+    }
+}
+
+/// Space under the plot area of a lane
+fn below<G: Plot>(lane: &G) -> f64 {
+    let (area, size) = lane.area();
+    lane.size().h - area.y - size.h
 }
 
 impl<'a> Graph for Track<'a> {
@@ -70,24 +171,95 @@ impl<'a> Graph for Track<'a> {
             &mut self
                 .hits
                 .renderables()
-                .iter_mut()
-                .map(|x| match x {
-                    Renderable::Rect(p, d, s) => {
-                        p.y += offset;
-                        Renderable::Rect(*p, *d, *s)
-                    }
-                    Renderable::Line(p1, p2, s) => {
-                        p1.y += offset;
-                        p2.y += offset;
-                        Renderable::Line(*p1, *p2, *s)
-                    }
-                })
+                .into_iter()
+                .map(|x| x.translate(0.0, offset))
                 .collect(),
         );
+        let mut offset = offset + self.hits.size().h;
+        if let Some(waveform) = &self.waveform {
+            renderables.append(
+                &mut waveform
+                    .renderables()
+                    .into_iter()
+                    .map(|x| x.translate(0.0, offset))
+                    .collect(),
+            );
+            offset += waveform.size().h;
+        }
+        if let Some(spectrogram) = &self.spectrogram {
+            renderables.append(
+                &mut spectrogram
+                    .renderables()
+                    .into_iter()
+                    .map(|x| x.translate(0.0, offset))
+                    .collect(),
+            );
+        }
 
         renderables
     }
 }
 
 #[cfg(test)]
-mod tests {}
+mod tests {
+    use super::*;
+    use crate::{Annotated, Annotation};
+
+    #[test]
+    fn waveform_lane_below_hits() {
+        let blocks = [Block(4.0, 1.0), Block(4.0, 2.0)];
+        let samples = vec![0.0f32; 8000];
+        let track = Track::new(&blocks, &blocks);
+        let height = track.size.h;
+        let track = track.with_waveform(Waveform::new(&samples[..], 1000));
+
+        assert_eq!(track.size.w, 8.0 * 20.0 + 80.0, "same time axis");
+        assert_eq!(track.size.h, height + 80.0 + 80.0, "lane added below");
+    }
+
+    #[test]
+    fn viewport_clips_audio_lanes() {
+        let blocks = [Block(4.0, 1.0), Block(4.0, 2.0)];
+        let samples = vec![0.0f32; 8000];
+        let clipped = Track::new(&blocks, &blocks)
+            .with_viewport(Viewport::new(2.0, 6.0))
+            .with_waveform(Waveform::new(&samples[..], 1000))
+            .with_spectrogram(Spectrogram::new(&samples[..], 1000));
+        let early = Track::new(&blocks, &blocks)
+            .with_waveform(Waveform::new(&samples[..], 1000))
+            .with_spectrogram(Spectrogram::new(&samples[..], 1000))
+            .with_viewport(Viewport::new(2.0, 6.0));
+
+        assert_eq!(clipped.size.w, 4.0 * 20.0 + 80.0, "window width");
+        assert_eq!(early.size.w, clipped.size.w, "whichever comes first");
+    }
+
+    #[test]
+    fn plot_area_spans_lanes() {
+        let blocks = [Block(4.0, 1.0), Block(4.0, 2.0)];
+        let samples = vec![0.0f32; 8000];
+        let track = Track::new(&blocks, &blocks).with_waveform(Waveform::new(&samples[..], 1000));
+        let (area, size) = track.area();
+
+        assert_eq!((area.x, area.y), (40.0, 40.0));
+        assert_eq!(size.w, 8.0 * 20.0);
+        assert_eq!(size.h, track.size.h - 80.0, "down to the waveform plot");
+        assert_eq!(track.position(4.0, 2.0).x, 40.0 + 80.0);
+    }
+
+    #[test]
+    fn annotations_beneath_over_lane_backgrounds() {
+        let blocks = [Block(4.0, 1.0), Block(4.0, 2.0)];
+        let track = Track::new(&blocks, &blocks);
+        let at = track.roll.renderables().len() + track.hits.grid().len();
+        let renderables = Annotated::new(track)
+            .with_annotation(Annotation::marker(4.0, "drop"))
+            .with_beneath()
+            .renderables();
+
+        match &renderables[at] {
+            Renderable::Rect(pos, size, _) => assert_eq!((pos.x, size.w), (40.0 + 80.0, 1.0)),
+            _ => panic!("expected marker after the grid of the hits"),
+        }
+    }
+}
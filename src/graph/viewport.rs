@@ -0,0 +1,104 @@
+use super::{label, CHAR_HEIGHT, CHAR_WIDTH};
+use crate::{Block, Point, Renderable, Style};
+
+/// Window onto a block sequence, from `start` to `end` in duration units,
+/// optionally limited to a range of values
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct Viewport {
+    start: f64,
+    end: f64,
+    values: Option<(f64, f64)>,
+}
+
+impl Viewport {
+    pub fn new(start: f64, end: f64) -> Self {
+        assert!(start < end, "viewport has to end after it starts");
+        Self {
+            start: start.max(0.0),
+            end,
+            values: None,
+        }
+    }
+
+    /// Values from `lo` to `hi` fill the height, whatever the blocks hold
+    pub fn with_values(mut self, lo: f64, hi: f64) -> Self {
+        self.values = Some((lo.min(hi), lo.max(hi)));
+        self
+    }
+
+    pub fn start(&self) -> f64 {
+        self.start
+    }
+
+    pub fn end(&self) -> f64 {
+        self.end
+    }
+
+    pub fn values(&self) -> Option<(f64, f64)> {
+        self.values
+    }
+
+    /// Index of the first block in the window and the blocks intersecting it,
+    /// the ones crossing its edges cut short
+    pub(super) fn clip(&self, blocks: &[Block]) -> (usize, Vec<Block>) {
+        let mut time = 0.0;
+        let mut first = None;
+        let mut visible = Vec::new();
+        for (idx, block) in blocks.iter().enumerate() {
+            let (from, to) = (time, time + block.0);
+            time = to;
+            let (from, to) = (from.max(self.start), to.min(self.end));
+            if to > from {
+                first.get_or_insert(idx);
+                visible.push(Block(to - from, block.1));
+            }
+        }
+        (first.unwrap_or(0), visible)
+    }
+
+    /// Window start and end time above the plot area, from `left` to `right`
+    pub(super) fn labels(
+        &self,
+        visible: &[Block],
+        left: f64,
+        right: f64,
+        top: f64,
+    ) -> Vec<Renderable> {
+        let end = self.start + visible.iter().map(|x| x.0).sum::<f64>();
+        let style = Style::color(0x909090);
+        let y = top - CHAR_HEIGHT * 2.0;
+        let end = label(end);
+        vec![
+            Renderable::Text(Point { x: left, y }, label(self.start), style),
+            Renderable::Text(
+                Point {
+                    x: right - end.chars().count() as f64 * CHAR_WIDTH,
+                    y,
+                },
+                end,
+                style,
+            ),
+        ]
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn clips_blocks_at_edges() {
+        let blocks = [
+            Block(4.0, 1.0),
+            Block(4.0, 2.0),
+            Block(4.0, 3.0),
+            Block(4.0, 4.0),
+        ];
+        let (first, visible) = Viewport::new(6.0, 13.0).clip(&blocks);
+        let visible: Vec<(f64, f64)> = visible.iter().map(|x| (x.0, x.1)).collect();
+
+        assert_eq!(first, 1);
+        assert_eq!(visible, vec![(2.0, 2.0), (4.0, 3.0), (1.0, 4.0)]);
+        assert!(Viewport::new(20.0, 30.0).clip(&blocks).1.is_empty());
+    }
+}
@@ -0,0 +1,453 @@
+use super::{left_tick, ticks, Graph, Plot, Scalable, Viewport, CHAR_HEIGHT};
+use crate::scale::Scale;
+use crate::{Block, Dimension, Meta, Point, Renderable, ShapeRenderer, Style};
+
+/// Quietest amplitude drawn on scales undefined at silence, -60 dB
+const FLOOR: f64 = 0.001;
+
+/// Borrowed sample buffer, interleaved when there is more than one channel
+#[derive(Copy, Clone)]
+pub enum Samples<'a> {
+    F32(&'a [f32]),
+    I16(&'a [i16]),
+}
+
+impl<'a> From<&'a [f32]> for Samples<'a> {
+    fn from(samples: &'a [f32]) -> Self {
+        Self::F32(samples)
+    }
+}
+
+impl<'a> From<&'a [i16]> for Samples<'a> {
+    fn from(samples: &'a [i16]) -> Self {
+        Self::I16(samples)
+    }
+}
+
+impl<'a> Samples<'a> {
+    pub fn len(&self) -> usize {
+        match self {
+            Self::F32(x) => x.len(),
+            Self::I16(x) => x.len(),
+        }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Sample normalized to -1..=1
+    pub fn get(&self, idx: usize) -> f32 {
+        match self {
+            Self::F32(x) => x[idx],
+            Self::I16(x) => x[idx] as f32 / 32768.0,
+        }
+    }
+}
+
+/// Frames of an interleaved buffer laid out on the block time axis
+#[derive(Copy, Clone)]
+pub(super) struct Timeline {
+    frames: usize,
+    sample_rate: u32,
+    tempo: f64,
+    viewport: Option<Viewport>,
+}
+
+impl Timeline {
+    pub(super) fn new(
+        len: usize,
+        channels: usize,
+        sample_rate: u32,
+        tempo: f64,
+        viewport: Option<Viewport>,
+    ) -> Self {
+        Self {
+            frames: len / channels,
+            sample_rate,
+            tempo,
+            viewport,
+        }
+    }
+
+    pub(super) fn frames(&self) -> usize {
+        self.frames
+    }
+
+    /// Time in block duration units after the given number of frames
+    pub(super) fn time(&self, frames: usize) -> f64 {
+        frames as f64 / self.sample_rate as f64 * self.tempo
+    }
+
+    /// Length of the whole buffer in block duration units
+    pub(super) fn duration(&self) -> f64 {
+        self.time(self.frames)
+    }
+
+    /// First frame drawn and the one after the last, all of them unless there
+    /// is a viewport
+    pub(super) fn window(&self) -> (usize, usize) {
+        let frame = |time: f64| {
+            let frame = (time / self.tempo * self.sample_rate as f64).round();
+            (frame.max(0.0) as usize).min(self.frames)
+        };
+        match self.viewport {
+            Some(viewport) => (frame(viewport.start()), frame(viewport.end())),
+            None => (0, self.frames),
+        }
+    }
+
+    /// Time of the first frame drawn
+    pub(super) fn start(&self) -> f64 {
+        self.time(self.window().0)
+    }
+
+    /// Whole pixels spanned by the frames drawn, at `unit` pixels per block
+    /// duration unit
+    pub(super) fn width(&self, unit: f64) -> f64 {
+        let (start, end) = self.window();
+        (self.time(end - start) * unit).ceil()
+    }
+}
+
+pub struct Waveform<'a> {
+    size: Dimension,
+    base: Block,
+    samples: Samples<'a>,
+    sample_rate: u32,
+    channels: usize,
+    tempo: f64,
+    y_scale: Option<Scale>,
+    viewport: Option<Viewport>,
+}
+
+impl<'a> Waveform<'a> {
+    pub fn new<T>(samples: T, sample_rate: u32) -> Self
+    where
+        T: Into<Samples<'a>>,
+    {
+        assert!(sample_rate > 0, "there has to be a sample rate");
+        let mut waveform = Self {
+            size: Dimension { w: 0.0, h: 0.0 },
+            base: Block(20.0, 20.0),
+            samples: samples.into(),
+            sample_rate,
+            channels: 1,
+            tempo: 1.0,
+            y_scale: None,
+            viewport: None,
+        };
+        waveform.resize();
+        waveform
+    }
+
+    /// Interleaved channels, drawn as stacked lanes
+    pub fn with_channels(mut self, channels: usize) -> Self {
+        self.channels = channels.max(1);
+        self.resize();
+        self
+    }
+
+    /// Block duration units per second, so time lines up with `Roll` and `Hits`.
+    /// For durations in beats, this is beats per minute divided by 60.
+    pub fn with_tempo(mut self, units_per_second: f64) -> Self {
+        self.tempo = units_per_second;
+        self.resize();
+        self
+    }
+
+    /// Maps the magnitude of samples onto each half of a lane, labelled with
+    /// ticks. Full scale reaches the edge of the lane, silence its middle or
+    /// -60 dB where the scale is undefined at zero.
+    pub fn with_y_scale(mut self, scale: Scale) -> Self {
+        self.y_scale = Some(scale);
+        self
+    }
+
+    /// Draws only the window of time, its values ignored. A window past the
+    /// last sample leaves the plot empty.
+    pub fn with_viewport(mut self, viewport: Viewport) -> Self {
+        self.viewport = Some(viewport);
+        self.resize();
+        self
+    }
+
+    fn timeline(&self) -> Timeline {
+        Timeline::new(
+            self.samples.len(),
+            self.channels,
+            self.sample_rate,
+            self.tempo,
+            self.viewport,
+        )
+    }
+
+    /// Length in block duration units
+    pub fn duration(&self) -> f64 {
+        self.timeline().duration()
+    }
+
+    fn lane(&self) -> f64 {
+        self.base.1 * 4.0
+    }
+
+    fn resize(&mut self) {
+        self.size = Dimension {
+            w: self.timeline().width(self.base.0),
+            h: self.lane() * self.channels as f64,
+        };
+        let &Block(dw, dh) = self.padding();
+        let &Dimension { w: mw, h: mh } = self.margin();
+        self.size.w += (self.base.0 * dw * 2.0) + mw * 2.0;
+        self.size.h += (self.base.1 * dh * 2.0) + mh * 2.0;
+    }
+
+    /// Scale positions of the middle and edge of a lane
+    fn span(scale: &Scale) -> (f64, f64) {
+        let lo = match scale.forward(0.0) {
+            lo if lo.is_finite() => lo,
+            _ => scale.forward(FLOOR),
+        };
+        (lo, scale.forward(1.0))
+    }
+
+    /// Share of the half lane a sample reaches, towards the top when positive
+    fn reach(&self, sample: f32) -> f64 {
+        let sample = sample.clamp(-1.0, 1.0) as f64;
+        let scale = match &self.y_scale {
+            Some(scale) => scale,
+            None => return sample,
+        };
+        let (lo, hi) = Self::span(scale);
+        match (scale.forward(sample.abs()) - lo) / (hi - lo) {
+            t if t.is_finite() => sample.signum() * t.clamp(0.0, 1.0),
+            _ => 0.0,
+        }
+    }
+
+    /// Ticks and labels of the upper half of a lane, if it is scaled
+    fn axis(&self, left: f64, center: f64) -> Vec<Renderable> {
+        let scale = match &self.y_scale {
+            Some(scale) => scale,
+            None => return Vec::new(),
+        };
+        let half = self.lane() / 2.0;
+        let (lo, hi) = Self::span(scale);
+        ticks(scale, (lo, hi), half, CHAR_HEIGHT * 2.0)
+            .into_iter()
+            .flat_map(|(position, value)| {
+                left_tick(left, center - (position - lo) / (hi - lo) * half, value)
+            })
+            .collect()
+    }
+
+    /// Lowest and highest sample of a channel within each pixel column
+    fn envelope(&self, channel: usize, columns: usize) -> Vec<(f32, f32)> {
+        let (first, last) = self.timeline().window();
+        let frames = last - first;
+        (0..columns)
+            .map(|column| {
+                let start = first + frames * column / columns;
+                let end = (first + frames * (column + 1) / columns)
+                    .max(start + 1)
+                    .min(last);
+                (start..end)
+                    .map(|frame| self.samples.get(frame * self.channels + channel))
+                    .fold((f32::MAX, f32::MIN), |(lo, hi), x| (lo.min(x), hi.max(x)))
+            })
+            .collect()
+    }
+}
+
+impl<'a> Scalable for Waveform<'a> {
+    fn with_base(mut self, base: Block) -> Self {
+        self.base = base;
+        self.resize();
+        self
+    }
+}
+
+impl<'a> Plot for Waveform<'a> {
+    /// Amplitude within the lane of the first channel
+    fn position(&self, time: f64, value: f64) -> Point {
+        let (area, _) = self.area();
+        let start = self.timeline().start();
+        let half = self.lane() / 2.0;
+        Point {
+            x: area.x + (time - start) * self.base.0,
+            y: area.y + half - self.reach(value as f32) * half,
+        }
+    }
+}
+
+impl<'a> Graph for Waveform<'a> {
+    fn size(&self) -> &Dimension {
+        &self.size
+    }
+
+    fn base(&self) -> &Block {
+        &self.base
+    }
+
+    fn draw<T>(&self, renderer: &mut T) -> Vec<u8>
+    where
+        T: ShapeRenderer,
+    {
+        for rect in self.renderables() {
+            renderer.draw(rect);
+        }
+
+        renderer.get_buffer().to_vec()
+    }
+
+    fn renderables(&self) -> Vec<Renderable> {
+        let &Block(dw, dh) = self.padding();
+        let &Dimension { w: mw, h: mh } = self.margin();
+        let left = (self.base.0 * dw) + mw;
+        let top = (self.base.1 * dh) + mh;
+        let columns = (self.size.w - left * 2.0).max(0.0) as usize;
+        let half = self.lane() / 2.0;
+        let axis = Style::color(0x505050);
+        let style = Style::color(0x6495ED);
+        let mut renderables = self.grid();
+        let (start, end) = self.timeline().window();
+        if start == end {
+            return renderables;
+        }
+        let mut lanes = Vec::new();
+        for channel in 0..self.channels {
+            let center = top + self.lane() * channel as f64 + half;
+            renderables.push(Renderable::Rect(
+                Point { x: left, y: center },
+                Dimension {
+                    w: columns as f64,
+                    h: 1.0,
+                },
+                axis,
+            ));
+            renderables.append(&mut self.axis(left, center));
+            let mut lane = Vec::new();
+            for (x, (lo, hi)) in self.envelope(channel, columns).into_iter().enumerate() {
+                let y1 = center - self.reach(hi) * half;
+                let y2 = center - self.reach(lo) * half;
+                lane.push(Renderable::Rect(
+                    Point {
+                        x: left + x as f64,
+                        y: y1.floor(),
+                    },
+                    Dimension {
+                        w: 1.0,
+                        h: (y2 - y1).ceil().max(1.0),
+                    },
+                    style,
+                ));
+            }
+            lanes.push(Renderable::Group(
+                lane,
+                Meta::class("channel").with_data("channel", channel),
+            ));
+        }
+        renderables.push(Renderable::Group(lanes, Meta::class("data")));
+        renderables
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::graph::data;
+
+    #[test]
+    #[should_panic(expected = "sample rate")]
+    fn zero_sample_rate() {
+        Waveform::new(&[0.0f32; 4][..], 0);
+    }
+
+    #[test]
+    fn width_follows_duration() {
+        let samples = vec![0.0f32; 44100 * 2];
+        let waveform = Waveform::new(&samples[..], 44100).with_tempo(2.0);
+
+        assert_eq!(waveform.duration(), 4.0, "two seconds at 120bpm");
+        assert_eq!(waveform.size.w, 4.0 * 20.0 + 80.0, "waveform width");
+        assert_eq!(waveform.size.h, 80.0 + 80.0, "waveform height");
+    }
+
+    #[test]
+    fn envelope_per_column() {
+        let samples: Vec<i16> = (0..1000)
+            .map(|i| if i < 500 { i16::MAX } else { i16::MIN })
+            .collect();
+        let waveform = Waveform::new(&samples[..], 1000);
+        let envelope = waveform.envelope(0, 4);
+
+        assert_eq!(envelope.len(), 4);
+        assert!(envelope[0].0 > 0.99 && envelope[0].1 > 0.99);
+        assert_eq!(envelope[3], (-1.0, -1.0));
+    }
+
+    #[test]
+    fn stereo_lanes() {
+        let samples = [0.5f32, -0.5, 0.5, -0.5];
+        let waveform = Waveform::new(&samples[..], 4).with_channels(2);
+
+        assert_eq!(waveform.timeline().frames(), 2);
+        assert_eq!(waveform.envelope(0, 1), vec![(0.5, 0.5)]);
+        assert_eq!(waveform.envelope(1, 1), vec![(-0.5, -0.5)]);
+        assert_eq!(waveform.size.h, 160.0 + 80.0, "stacked lanes");
+        match &data(waveform.renderables())[1] {
+            Renderable::Group(columns, meta) => {
+                assert_eq!(columns.len(), 10, "a column per pixel");
+                assert_eq!(meta.get_data(), [("channel".to_string(), "1".to_string())]);
+            }
+            _ => panic!("expected second channel"),
+        }
+    }
+
+    #[test]
+    fn decibel_reach() {
+        let samples = [0.0f32; 4];
+        let waveform = Waveform::new(&samples[..], 4).with_y_scale(Scale::Decibel);
+        let close = |sample: f32, expected: f64| (waveform.reach(sample) - expected).abs() < 1e-6;
+
+        assert!(close(1.0, 1.0));
+        assert!(close(0.1, 2.0 / 3.0), "-20 dB of -60");
+        assert!(close(-0.01, -1.0 / 3.0));
+        assert!(close(0.0001, 0.0), "below the floor");
+        assert!(close(0.0, 0.0));
+    }
+
+    #[test]
+    fn viewport_window() {
+        let samples: Vec<f32> = (0..1000)
+            .map(|i| if i < 500 { 0.5 } else { -0.5 })
+            .collect();
+        let waveform = Waveform::new(&samples[..], 1000).with_viewport(Viewport::new(0.5, 2.0));
+
+        assert_eq!(
+            waveform.timeline().window(),
+            (500, 1000),
+            "clipped at the last sample"
+        );
+        assert_eq!(waveform.size.w, 10.0 + 80.0);
+        assert_eq!(waveform.envelope(0, 2), vec![(-0.5, -0.5), (-0.5, -0.5)]);
+
+        let past = Waveform::new(&samples[..], 1000).with_viewport(Viewport::new(2.0, 3.0));
+        assert_eq!(past.size.w, 80.0);
+        assert_eq!(
+            past.renderables().len(),
+            past.grid().len(),
+            "nothing to draw"
+        );
+    }
+
+    #[test]
+    fn plotted_over_time() {
+        let samples = vec![0.0f32; 4000];
+        let waveform = Waveform::new(&samples[..], 1000).with_viewport(Viewport::new(1.0, 3.0));
+
+        assert_eq!(waveform.position(2.0, 0.0).x, 40.0 + 20.0);
+        assert_eq!(waveform.position(2.0, 1.0).y, 40.0, "full scale at the top");
+        assert_eq!(waveform.position(2.0, -0.5).y, 40.0 + 60.0);
+    }
+}
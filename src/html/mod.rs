@@ -0,0 +1,168 @@
+//! Standalone HTML page around an SVG, zoomed with the wheel, panned by
+//! dragging and telling each block apart on hover. Needs no external assets.
+
+use crate::{svg, Dimension, Graph, ImageRenderer, Meta, Renderable, ShapeRenderer};
+use rsound_output::*;
+
+/// Zoom, pan and tooltips, after `size` and `tips` are declared
+const SCRIPT: &str = include_str!("script.js");
+
+const STYLE: &str = "body { margin: 0; background: #060910; color: #E0E0E0; font: 12px monospace; }
+svg { display: block; width: 100%; height: auto; cursor: grab; user-select: none; }
+#tip { display: none; position: absolute; padding: 4px 6px; white-space: pre; pointer-events: none; background: rgba(0, 0, 0, 0.8); border: 1px solid #808080; }
+";
+
+pub struct Renderer {
+    svg: svg::Renderer,
+    size: Dimension,
+    title: String,
+    /// Text of each note, in the order they are drawn
+    tips: Vec<String>,
+}
+
+impl Renderer {
+    pub fn new(size: &Dimension) -> Self {
+        Self {
+            svg: svg::Renderer::new(size),
+            size: *size,
+            title: "graph".to_string(),
+            tips: Vec::new(),
+        }
+    }
+
+    pub fn with_title(mut self, title: &str) -> Self {
+        self.title = title.to_string();
+        self
+    }
+
+    /// Tooltips for the notes of the graph, the shapes tagged with a duration,
+    /// saying their title or else their data
+    pub fn with_tooltips<G: Graph>(mut self, graph: &G) -> Self {
+        self.tips = Vec::new();
+        tips(&graph.renderables(), &mut self.tips);
+        self
+    }
+
+    /// Declarations the script starts from
+    fn data(&self) -> String {
+        let tips = self
+            .tips
+            .iter()
+            .map(|text| format!("'{}'", quote(text)))
+            .collect::<Vec<String>>()
+            .join(",\n  ");
+        format!(
+            "const size = [{}, {}];\nconst tips = [\n  {}\n];\n",
+            self.size.w, self.size.h, tips
+        )
+    }
+}
+
+/// Walks the shapes in drawing order for the tips of those tagged as notes
+fn tips(renderables: &[Renderable], tips: &mut Vec<String>) {
+    for renderable in renderables {
+        let (shapes, meta) = match renderable {
+            Renderable::Tagged(shape, meta) => (std::slice::from_ref(&**shape), meta),
+            Renderable::Group(shapes, meta) => (shapes.as_slice(), meta),
+            _ => continue,
+        };
+        tips.extend(tip(meta));
+        self::tips(shapes, tips);
+    }
+}
+
+/// Title of a note, or else its data
+fn tip(meta: &Meta) -> Option<String> {
+    let data = meta.get_data();
+    data.iter().find(|(key, _)| key == "duration")?;
+    Some(match meta.get_title() {
+        Some(title) => title.to_string(),
+        None => data
+            .iter()
+            .map(|(key, value)| format!("{} {}", key, value))
+            .collect::<Vec<String>>()
+            .join(", "),
+    })
+}
+
+/// Text safe inside a single quoted script string
+fn quote(text: &str) -> String {
+    text.replace('\\', "\\\\")
+        .replace('\'', "\\'")
+        .replace('<', "\\x3C")
+        .replace('\n', "\\n")
+}
+
+fn escape(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+}
+
+impl ShapeRenderer for Renderer {
+    fn draw(&mut self, shape: Renderable) {
+        self.svg.draw(shape);
+    }
+}
+
+impl Buffer for Renderer {
+    fn get_buffer(&self) -> &[u8] {
+        self.svg.get_buffer()
+    }
+}
+
+impl OutputRenderer for Renderer {
+    fn get_header(&self) -> Option<Vec<u8>> {
+        let mut header = format!(
+            "<!DOCTYPE html>\n<html>\n<head>\n<meta charset='utf-8'>\n<title>{}</title>\n<style>\n{}</style>\n</head>\n<body>\n",
+            escape(&self.title),
+            STYLE
+        )
+        .into_bytes();
+        header.append(&mut self.svg.get_header().unwrap_or_default());
+        Some(header)
+    }
+
+    fn get_footer(&self) -> Option<Vec<u8>> {
+        let mut footer = self.svg.get_footer().unwrap_or_default();
+        footer.append(
+            &mut format!(
+                "\n<div id='tip'></div>\n<script>\n{}{}</script>\n</body>\n</html>\n",
+                self.data(),
+                SCRIPT
+            )
+            .into_bytes(),
+        );
+        Some(footer)
+    }
+}
+
+impl ImageRenderer for Renderer {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{Block, Roll};
+
+    #[test]
+    fn page_around_svg() {
+        let blocks = [Block(4.0, 1.0), Block(2.0, 3.0)];
+        let roll = Roll::new(&blocks);
+        let mut renderer = Renderer::new(roll.size())
+            .with_title("<roll>")
+            .with_tooltips(&roll);
+        roll.draw(&mut renderer);
+        let header = String::from_utf8(renderer.get_header().unwrap()).unwrap();
+        let footer = String::from_utf8(renderer.get_footer().unwrap()).unwrap();
+
+        assert!(header.starts_with("<!DOCTYPE html>"));
+        assert!(header.contains("<title>&lt;roll&gt;</title>"));
+        assert!(header.ends_with("xmlns='http://www.w3.org/2000/svg'>\n"));
+        assert!(renderer.get_buffer().starts_with(b"<g class='grid'>"));
+        assert!(footer.starts_with("</svg>"));
+        assert!(footer.contains(
+            "const tips = [\n  'duration 4, intensity 1',\n  'duration 2, intensity 3'\n];"
+        ));
+        assert!(footer.ends_with("</html>\n"));
+    }
+}
@@ -0,0 +1,60 @@
+const svg = document.querySelector('svg');
+const tip = document.getElementById('tip');
+// Notes in drawing order, matching the tips
+const notes = [...svg.querySelectorAll('[data-duration]')];
+let view = [0, 0, size[0], size[1]];
+let drag = null;
+
+function show() {
+  svg.setAttribute('viewBox', view.join(' '));
+}
+
+// Pointer position in drawing coordinates
+function local(event) {
+  const rect = svg.getBoundingClientRect();
+  return [
+    view[0] + ((event.clientX - rect.left) / rect.width) * view[2],
+    view[1] + ((event.clientY - rect.top) / rect.height) * view[3],
+  ];
+}
+
+svg.addEventListener('wheel', (event) => {
+  event.preventDefault();
+  const [x, y] = local(event);
+  const width = Math.min(size[0], Math.max(size[0] / 64, view[2] * Math.exp(event.deltaY * 0.002)));
+  const scale = width / view[2];
+  view = [x - (x - view[0]) * scale, y - (y - view[1]) * scale, view[2] * scale, view[3] * scale];
+  show();
+}, { passive: false });
+
+svg.addEventListener('mousedown', (event) => {
+  event.preventDefault();
+  drag = [event.clientX, event.clientY, view[0], view[1]];
+});
+window.addEventListener('mouseup', () => {
+  drag = null;
+});
+svg.addEventListener('dblclick', () => {
+  view = [0, 0, size[0], size[1]];
+  show();
+});
+
+svg.addEventListener('mousemove', (event) => {
+  if (drag) {
+    const rect = svg.getBoundingClientRect();
+    view[0] = drag[2] - ((event.clientX - drag[0]) / rect.width) * view[2];
+    view[1] = drag[3] - ((event.clientY - drag[1]) / rect.height) * view[3];
+    show();
+  }
+  const note = event.target.closest('[data-duration]');
+  const text = note ? tips[notes.indexOf(note)] : undefined;
+  tip.textContent = text || '';
+  tip.style.display = text ? 'block' : 'none';
+  tip.style.left = event.pageX + 12 + 'px';
+  tip.style.top = event.pageY + 12 + 'px';
+});
+svg.addEventListener('mouseleave', () => {
+  tip.style.display = 'none';
+});
+
+show();
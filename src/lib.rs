@@ -1,13 +1,26 @@
 use rsound_output::*;
+pub mod animation;
+pub mod colormap;
+pub mod fft;
+pub mod gif;
+pub mod html;
 pub mod ppm;
+pub mod scale;
 pub mod svg;
 pub mod writer;
+pub mod y4m;
 
 mod graph;
 pub use crate::graph::*;
 
+mod color;
+pub use color::*;
+
 mod style;
-use style::*;
+pub use style::*;
+
+mod meta;
+pub use meta::*;
 
 // Primitives
 // ==========
@@ -23,6 +36,19 @@ pub struct Dimension {
     w: f64,
     h: f64,
 }
+impl Dimension {
+    pub fn new(w: f64, h: f64) -> Self {
+        Self { w, h }
+    }
+
+    pub fn width(&self) -> f64 {
+        self.w
+    }
+
+    pub fn height(&self) -> f64 {
+        self.h
+    }
+}
 
 #[derive(Debug)]
 pub struct Block(f64, f64);
@@ -49,7 +75,100 @@ pub trait ShapeRenderer: Buffer {
 
 pub trait ImageRenderer: ShapeRenderer + OutputRenderer {}
 
+/// Step of an open path
+#[derive(Debug, Copy, Clone)]
+pub enum PathSegment {
+    MoveTo(Point),
+    LineTo(Point),
+    /// Cubic bezier through two control points
+    CubicTo(Point, Point, Point),
+}
+
+/// Straight pieces each cubic of a path is drawn with
+pub(crate) const CURVE_STEPS: usize = 16;
+
+/// Point on a cubic bezier at `t` within 0..=1
+pub(crate) fn bezier(p0: Point, p1: Point, p2: Point, p3: Point, t: f64) -> Point {
+    let u = 1.0 - t;
+    let (a, b, c, d) = (u * u * u, 3.0 * u * u * t, 3.0 * u * t * t, t * t * t);
+    Point {
+        x: a * p0.x + b * p1.x + c * p2.x + d * p3.x,
+        y: a * p0.y + b * p1.y + c * p2.y + d * p3.y,
+    }
+}
+
+/// Points along a path, its curves cut into straight pieces
+pub(crate) fn flatten(segments: &[PathSegment]) -> Vec<Point> {
+    let mut points: Vec<Point> = Vec::new();
+    for segment in segments {
+        match *segment {
+            PathSegment::MoveTo(p) | PathSegment::LineTo(p) => points.push(p),
+            PathSegment::CubicTo(c1, c2, p) => {
+                let from = points.last().copied().unwrap_or(p);
+                for step in 1..=CURVE_STEPS {
+                    points.push(bezier(from, c1, c2, p, step as f64 / CURVE_STEPS as f64));
+                }
+            }
+        }
+    }
+    points
+}
+
+#[derive(Clone)]
 pub enum Renderable {
     Rect(Point, Dimension, Style),
     Line(Point, Point, Style),
+    Text(Point, String, Style),
+    Polygon(Vec<Point>, Style),
+    Path(Vec<PathSegment>, Style),
+    /// Shape standing for something, such as a note
+    Tagged(Box<Renderable>, Meta),
+    /// Shapes belonging together, such as the grid
+    Group(Vec<Renderable>, Meta),
+}
+
+impl Renderable {
+    /// Shape carrying metadata
+    pub fn with_meta(self, meta: Meta) -> Self {
+        Renderable::Tagged(Box::new(self), meta)
+    }
+
+    /// Moves the shape by the given offset
+    pub fn translate(self, dx: f64, dy: f64) -> Self {
+        let shift = |p: Point| Point {
+            x: p.x + dx,
+            y: p.y + dy,
+        };
+        match self {
+            Renderable::Rect(p, d, s) => Renderable::Rect(shift(p), d, s),
+            Renderable::Line(p1, p2, s) => Renderable::Line(shift(p1), shift(p2), s),
+            Renderable::Text(p, t, s) => Renderable::Text(shift(p), t, s),
+            Renderable::Polygon(ps, s) => {
+                Renderable::Polygon(ps.into_iter().map(shift).collect(), s)
+            }
+            Renderable::Path(segments, s) => Renderable::Path(
+                segments
+                    .into_iter()
+                    .map(|segment| match segment {
+                        PathSegment::MoveTo(p) => PathSegment::MoveTo(shift(p)),
+                        PathSegment::LineTo(p) => PathSegment::LineTo(shift(p)),
+                        PathSegment::CubicTo(c1, c2, p) => {
+                            PathSegment::CubicTo(shift(c1), shift(c2), shift(p))
+                        }
+                    })
+                    .collect(),
+                s,
+            ),
+            Renderable::Tagged(shape, meta) => {
+                Renderable::Tagged(Box::new(shape.translate(dx, dy)), meta)
+            }
+            Renderable::Group(shapes, meta) => Renderable::Group(
+                shapes
+                    .into_iter()
+                    .map(|shape| shape.translate(dx, dy))
+                    .collect(),
+                meta,
+            ),
+        }
+    }
 }
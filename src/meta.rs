@@ -0,0 +1,56 @@
+use std::fmt::Display;
+
+/// What a shape or group stands for, kept by outputs that can carry it
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Meta {
+    id: Option<String>,
+    classes: Vec<String>,
+    title: Option<String>,
+    data: Vec<(String, String)>,
+}
+
+impl Meta {
+    /// Space separated class names
+    pub fn class(names: &str) -> Self {
+        Self::default().with_class(names)
+    }
+
+    pub fn with_id(mut self, id: &str) -> Self {
+        self.id = Some(id.to_string());
+        self
+    }
+
+    pub fn with_class(mut self, names: &str) -> Self {
+        self.classes
+            .extend(names.split_whitespace().map(|x| x.to_string()));
+        self
+    }
+
+    /// Text shown on hover
+    pub fn with_title(mut self, title: &str) -> Self {
+        self.title = Some(title.to_string());
+        self
+    }
+
+    /// Named value, such as the duration of a note
+    pub fn with_data(mut self, key: &str, value: impl Display) -> Self {
+        self.data.push((key.to_string(), value.to_string()));
+        self
+    }
+
+    pub fn get_id(&self) -> Option<&str> {
+        self.id.as_deref()
+    }
+
+    pub fn get_classes(&self) -> &[String] {
+        &self.classes
+    }
+
+    pub fn get_title(&self) -> Option<&str> {
+        self.title.as_deref()
+    }
+
+    pub fn get_data(&self) -> &[(String, String)] {
+        &self.data
+    }
+}
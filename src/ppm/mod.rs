@@ -1,6 +1,12 @@
-use crate::{Color, Dimension, ImageRenderer, Point, Renderable, ShapeRenderer};
+use crate::{
+    bezier, Color, Dimension, ImageRenderer, PathSegment, Point, Renderable, ShapeRenderer,
+    CURVE_STEPS,
+};
 use rsound_output::*;
 
+mod font;
+
+#[derive(Clone)]
 pub struct Renderer {
     size: Dimension,
     buffer: Vec<u8>,
@@ -19,9 +25,35 @@ impl ShapeRenderer for Renderer {
             }
             Renderable::Line(p1, p2, style) => {
                 if let Some((color, thickness)) = style.get_frame() {
-                    self.line(p1, p2, color, thickness);
+                    self.line(p1, p2, color, thickness, style.get_dash());
+                } else if style.has_fill() {
+                    self.line(p1, p2, style.get_color(), 1.0, style.get_dash());
+                }
+            }
+            Renderable::Text(pos, text, style) => {
+                self.text(pos, &text, style.get_color());
+            }
+            Renderable::Polygon(points, style) => {
+                if style.has_fill() {
+                    self.polygon(&points, style.get_color());
+                }
+                if let Some((color, thickness)) = style.get_frame() {
+                    for (p1, p2) in points.iter().zip(points.iter().cycle().skip(1)) {
+                        self.line(*p1, *p2, color, thickness, style.get_dash());
+                    }
+                }
+            }
+            Renderable::Path(segments, style) => {
+                if let Some((color, thickness)) = style.get_frame() {
+                    self.path(&segments, color, thickness, style.get_dash());
                 } else if style.has_fill() {
-                    self.line(p1, p2, style.get_color(), 1.0);
+                    self.path(&segments, style.get_color(), 1.0, style.get_dash());
+                }
+            }
+            Renderable::Tagged(shape, _) => self.draw(*shape),
+            Renderable::Group(shapes, _) => {
+                for shape in shapes {
+                    self.draw(shape);
                 }
             }
         };
@@ -60,27 +92,27 @@ impl Renderer {
 
     fn rect(&mut self, pos: Point, size: Dimension, color: &Color) {
         let ystart = pos.y as usize;
-        let yend = (pos.y + size.h) as usize;
+        let yend = ((pos.y + size.h) as usize).min(self.size.h as usize);
         let xstart = pos.x as usize;
-        let xend = (pos.x + size.w) as usize;
         let width = self.size.w as usize;
+        let xend = ((pos.x + size.w) as usize).min(width);
 
         for y in ystart..yend {
             for x in xstart..xend {
                 let offset = (y * width * 3) + (x * 3);
-                self.buffer[offset] = color.0;
-                self.buffer[offset + 1] = color.1;
-                self.buffer[offset + 2] = color.2;
+                paint(&mut self.buffer, offset, color);
             }
         }
     }
 
     fn frame(&mut self, pos: Point, size: Dimension, color: &Color, thickness: f64) {
+        let (w, h) = (self.size.w as usize, self.size.h as usize);
         let mut pixel = |x: usize, y: usize| {
+            if x >= w || y >= h {
+                return;
+            }
             let offset = (y * (self.size.w as usize) * 3) + (x * 3);
-            self.buffer[offset] = color.0;
-            self.buffer[offset + 1] = color.1;
-            self.buffer[offset + 2] = color.2;
+            paint(&mut self.buffer, offset, color);
         };
         // top
         for y in (pos.y as usize)..((pos.y + thickness) as usize) {
@@ -107,51 +139,162 @@ impl Renderer {
         }
     }
 
-    fn line(&mut self, p1: Point, p2: Point, color: &Color, thickness: f64) {
-        let mut line = |x1: f64, x2: f64, y1: f64, y2: f64| {
-            let dx = x2 - x1;
-            let dy = y2 - y1;
-            let mut delta = 2.0 * dy - dx;
-            let mut y = y1;
-
-            for x in (x1 as usize)..(x2 as usize) {
-                let offset = (y as usize * (self.size.w as usize) * 3) + (x * 3);
-                self.buffer[offset] = color.0;
-                self.buffer[offset + 1] = color.1;
-                self.buffer[offset + 2] = color.2;
-
-                if delta > 0.0 {
-                    y += 1.0;
-                    delta -= 2.0 * dx;
+    // https://en.wikipedia.org/wiki/Bresenham's_line_algorithm
+    fn line(
+        &mut self,
+        p1: Point,
+        p2: Point,
+        color: &Color,
+        thickness: f64,
+        dash: Option<(f64, f64)>,
+    ) {
+        let (mut x, mut y) = (p1.x.round() as i64, p1.y.round() as i64);
+        let (x2, y2) = (p2.x.round() as i64, p2.y.round() as i64);
+        let dx = (x2 - x).abs();
+        let dy = -(y2 - y).abs();
+        let sx = if x < x2 { 1 } else { -1 };
+        let sy = if y < y2 { 1 } else { -1 };
+        let mut delta = dx + dy;
+        let mut travelled = 0.0;
+
+        loop {
+            let visible = match dash {
+                Some((on, off)) if on + off > 0.0 => travelled % (on + off) < on,
+                _ => true,
+            };
+            if visible {
+                self.dot(x, y, color, thickness);
+            }
+            if x == x2 && y == y2 {
+                break;
+            }
+            let double = 2 * delta;
+            let (mut moved_x, mut moved_y) = (false, false);
+            if double >= dy {
+                delta += dy;
+                x += sx;
+                moved_x = true;
+            }
+            if double <= dx {
+                delta += dx;
+                y += sy;
+                moved_y = true;
+            }
+            travelled += if moved_x && moved_y {
+                std::f64::consts::SQRT_2
+            } else {
+                1.0
+            };
+        }
+    }
+
+    /// Square brush of the given thickness, centered on the pixel
+    fn dot(&mut self, x: i64, y: i64, color: &Color, thickness: f64) {
+        let size = (thickness.round() as i64).max(1);
+        let width = self.size.w as i64;
+        let height = self.size.h as i64;
+        for py in (y - size / 2)..(y - size / 2 + size) {
+            for px in (x - size / 2)..(x - size / 2 + size) {
+                if px < 0 || py < 0 || px >= width || py >= height {
+                    continue;
                 }
-                delta += 2.0 * dy;
+                let offset = (py as usize * width as usize * 3) + (px as usize * 3);
+                paint(&mut self.buffer, offset, color);
             }
-        };
-        line(p1.x, p2.x, p1.y, p2.y);
-        for i in 0..=(thickness as usize) {
-            line(p1.x - (i as f64), p2.x, p1.y, p2.y);
-            line(p1.x + (i as f64), p2.x, p1.y, p2.y);
         }
     }
 
-    // https://en.wikipedia.org/wiki/Bresenham's_line_algorithm
-    // fn line(&mut self, p1: Point, p2: Point, color: &Color, thickness: f64) {
-    // let dx = p1.x - p2.x;
-    // let dy = p2.y - p2.y;
-    // let mut delta = 2.0 * dy - dx;
-    // let mut y = p1.y;
-
-    // for x in (p1.x as usize)..(p2.x as usize) {
-    //     let offset = (y as usize * (self.size.w as usize) * 3) + (x * 3);
-    //     self.buffer[offset] = color.0;
-    //     self.buffer[offset + 1] = color.1;
-    //     self.buffer[offset + 2] = color.2;
-
-    //     if delta > 0.0 {
-    //         y = y + 1.0;
-    //         delta = delta - 2.0*dx;
-    //     }
-    //     delta = delta + 2.0*dy;
-    // }
-    // }
+    /// Even-odd scanline fill
+    fn polygon(&mut self, points: &[Point], color: &Color) {
+        if points.len() < 3 {
+            return;
+        }
+        let width = self.size.w as usize;
+        let top = points.iter().map(|p| p.y).fold(f64::MAX, f64::min).max(0.0) as usize;
+        let bottom = (points.iter().map(|p| p.y).fold(f64::MIN, f64::max).ceil() as usize)
+            .min(self.size.h as usize);
+        for y in top..bottom {
+            let scan = y as f64 + 0.5;
+            let mut crossings: Vec<f64> = points
+                .iter()
+                .zip(points.iter().cycle().skip(1))
+                .filter(|(a, b)| (a.y <= scan) != (b.y <= scan))
+                .map(|(a, b)| a.x + (scan - a.y) / (b.y - a.y) * (b.x - a.x))
+                .collect();
+            crossings.sort_by(|a, b| a.total_cmp(b));
+            for pair in crossings.chunks(2) {
+                if let [from, to] = pair {
+                    let start = from.round().max(0.0) as usize;
+                    let end = (to.round().max(0.0) as usize).min(width);
+                    for x in start..end {
+                        paint(&mut self.buffer, (y * width * 3) + (x * 3), color);
+                    }
+                }
+            }
+        }
+    }
+
+    /// Curves are flattened to short segments
+    fn path(
+        &mut self,
+        segments: &[PathSegment],
+        color: &Color,
+        thickness: f64,
+        dash: Option<(f64, f64)>,
+    ) {
+        let mut at: Option<Point> = None;
+        for segment in segments {
+            match *segment {
+                PathSegment::MoveTo(p) => at = Some(p),
+                PathSegment::LineTo(p) => {
+                    if let Some(from) = at {
+                        self.line(from, p, color, thickness, dash);
+                    }
+                    at = Some(p);
+                }
+                PathSegment::CubicTo(c1, c2, p) => {
+                    if let Some(from) = at {
+                        let mut previous = from;
+                        for step in 1..=CURVE_STEPS {
+                            let next = bezier(from, c1, c2, p, step as f64 / CURVE_STEPS as f64);
+                            self.line(previous, next, color, thickness, dash);
+                            previous = next;
+                        }
+                    }
+                    at = Some(p);
+                }
+            }
+        }
+    }
+
+    fn text(&mut self, pos: Point, text: &str, color: &Color) {
+        let width = self.size.w as usize;
+        let height = self.size.h as usize;
+        for (i, c) in text.chars().enumerate() {
+            let left = pos.x as usize + i * font::ADVANCE;
+            for (row, bits) in font::glyph(c).iter().enumerate() {
+                for col in 0..font::WIDTH {
+                    if bits & (1 << (font::WIDTH - 1 - col)) == 0 {
+                        continue;
+                    }
+                    let x = left + col;
+                    let y = pos.y as usize + row;
+                    if x >= width || y >= height {
+                        continue;
+                    }
+                    let offset = (y * width * 3) + (x * 3);
+                    paint(&mut self.buffer, offset, color);
+                }
+            }
+        }
+    }
+}
+
+/// Blends color over the pixel at offset, according to its alpha
+fn paint(buffer: &mut [u8], offset: usize, color: &Color) {
+    let alpha = color.3 as u16;
+    for (i, channel) in [color.0, color.1, color.2].into_iter().enumerate() {
+        let dst = buffer[offset + i] as u16;
+        buffer[offset + i] = ((channel as u16 * alpha + dst * (255 - alpha) + 127) / 255) as u8;
+    }
 }
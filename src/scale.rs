@@ -0,0 +1,160 @@
+//! Axis scales, mapping values to positions along an axis.
+
+use std::fmt;
+use std::sync::Arc;
+
+type Mapping = Arc<dyn Fn(f64) -> f64 + Send + Sync>;
+
+#[derive(Clone, Default)]
+pub enum Scale {
+    #[default]
+    Linear,
+    Log10,
+    Log2,
+    /// Linear within the given distance of zero, logarithmic beyond
+    Symlog(f64),
+    /// Amplitude in decibels relative to full scale
+    Decibel,
+    /// Raised to the given exponent, keeping the sign
+    Power(f64),
+    /// Perceived pitch of a frequency in hertz
+    Mel,
+    /// Forward and inverse mapping
+    Custom(Mapping, Mapping),
+}
+
+impl fmt::Debug for Scale {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Scale::Linear => write!(f, "Linear"),
+            Scale::Log10 => write!(f, "Log10"),
+            Scale::Log2 => write!(f, "Log2"),
+            Scale::Symlog(threshold) => write!(f, "Symlog({})", threshold),
+            Scale::Decibel => write!(f, "Decibel"),
+            Scale::Power(exponent) => write!(f, "Power({})", exponent),
+            Scale::Mel => write!(f, "Mel"),
+            Scale::Custom(_, _) => write!(f, "Custom"),
+        }
+    }
+}
+
+impl Scale {
+    /// User supplied mapping, `inverse` undoing `forward`
+    pub fn custom<F, I>(forward: F, inverse: I) -> Self
+    where
+        F: Fn(f64) -> f64 + Send + Sync + 'static,
+        I: Fn(f64) -> f64 + Send + Sync + 'static,
+    {
+        Scale::Custom(Arc::new(forward), Arc::new(inverse))
+    }
+
+    /// Position of a value, not finite where the scale is undefined
+    pub fn forward(&self, value: f64) -> f64 {
+        match self {
+            Scale::Linear => value,
+            Scale::Log10 => value.log10(),
+            Scale::Log2 => value.log2(),
+            Scale::Symlog(threshold) => value.signum() * (1.0 + value.abs() / threshold).log10(),
+            Scale::Decibel => 20.0 * value.log10(),
+            Scale::Power(exponent) => value.signum() * value.abs().powf(*exponent),
+            Scale::Mel => 2595.0 * (1.0 + value / 700.0).log10(),
+            Scale::Custom(forward, _) => forward(value),
+        }
+    }
+
+    pub fn inverse(&self, position: f64) -> f64 {
+        match self {
+            Scale::Linear => position,
+            Scale::Log10 => 10f64.powf(position),
+            Scale::Log2 => 2f64.powf(position),
+            Scale::Symlog(threshold) => {
+                position.signum() * threshold * (10f64.powf(position.abs()) - 1.0)
+            }
+            Scale::Decibel => 10f64.powf(position / 20.0),
+            Scale::Power(exponent) => position.signum() * position.abs().powf(1.0 / exponent),
+            Scale::Mel => 700.0 * (10f64.powf(position / 2595.0) - 1.0),
+            Scale::Custom(_, inverse) => inverse(position),
+        }
+    }
+
+    /// About `count` values between `lo` and `hi`, evenly spaced in scale positions.
+    /// Logarithmic scales land on powers of their base.
+    pub fn ticks(&self, lo: f64, hi: f64, count: usize) -> Vec<f64> {
+        steps(self.forward(lo), self.forward(hi), count)
+            .into_iter()
+            .map(|position| self.inverse(position))
+            .collect()
+    }
+}
+
+/// Round multiples of 1, 2 or 5 times a power of ten within `lo..=hi`
+pub(crate) fn steps(lo: f64, hi: f64, count: usize) -> Vec<f64> {
+    let (lo, hi) = (lo.min(hi), lo.max(hi));
+    if !lo.is_finite() || !hi.is_finite() || (hi - lo).abs() < f64::EPSILON {
+        return Vec::new();
+    }
+    let raw = (hi - lo) / count.max(1) as f64;
+    let magnitude = 10f64.powf(raw.log10().floor());
+    let step = [1.0, 2.0, 5.0, 10.0]
+        .iter()
+        .map(|x| x * magnitude)
+        .find(|&x| x >= raw)
+        .unwrap_or(10.0 * magnitude);
+    let first = (lo / step).ceil() as i64;
+    let last = (hi / step).floor() as i64;
+    (first..=last).map(|i| i as f64 * step).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn roundtrips() {
+        let scales = [
+            Scale::Linear,
+            Scale::Log10,
+            Scale::Log2,
+            Scale::Symlog(1.0),
+            Scale::Decibel,
+            Scale::Power(0.5),
+            Scale::Mel,
+            Scale::custom(|x| x * 3.0, |x| x / 3.0),
+        ];
+        for scale in scales {
+            for value in [0.25, 3.0, 440.0] {
+                let back = scale.inverse(scale.forward(value));
+                assert!((back - value).abs() < 1e-9, "{:?} at {}", scale, value);
+            }
+        }
+        let symlog = Scale::Symlog(1.0);
+        assert!((symlog.inverse(symlog.forward(-5.0)) + 5.0).abs() < 1e-9);
+        assert!((Scale::Decibel.forward(0.1) + 20.0).abs() < 1e-9);
+    }
+
+    #[test]
+    fn log_ticks_on_powers() {
+        let close = |ticks: Vec<f64>, expected: &[f64]| {
+            ticks.len() == expected.len()
+                && ticks
+                    .iter()
+                    .zip(expected)
+                    .all(|(a, b)| (a - b).abs() < 1e-9)
+        };
+        assert!(close(
+            Scale::Log10.ticks(20.0, 20000.0, 4),
+            &[100.0, 1000.0, 10000.0]
+        ));
+        assert!(close(
+            Scale::Log2.ticks(1.0, 16.0, 4),
+            &[1.0, 2.0, 4.0, 8.0, 16.0]
+        ));
+    }
+
+    #[test]
+    fn round_steps() {
+        assert_eq!(steps(0.0, 10.0, 5), vec![0.0, 2.0, 4.0, 6.0, 8.0, 10.0]);
+        assert_eq!(steps(-60.0, 0.0, 3), vec![-60.0, -40.0, -20.0, 0.0]);
+        assert!(steps(1.0, 1.0, 5).is_empty());
+    }
+}
@@ -1,27 +1,22 @@
-#[derive(Copy, Clone)]
-pub struct Color(pub u8, pub u8, pub u8);
-
-impl From<u32> for Color {
-    fn from(raw: u32) -> Self {
-        Self(
-            ((raw >> 16) & 255) as u8,
-            ((raw >> 8) & 255) as u8,
-            (raw & 255) as u8,
-        )
-    }
-}
-
-impl Color {
-    pub fn rgb(&self) -> String {
-        format!("rgb({}, {}, {})", self.0, self.1, self.2)
-    }
-}
+use crate::Color;
 
 #[derive(Copy, Clone)]
 pub struct Style {
     color: Color,
     background: Option<Color>,
     border: Option<f64>,
+    dash: Option<(f64, f64)>,
+}
+
+impl From<Color> for Style {
+    fn from(color: Color) -> Self {
+        Self {
+            color,
+            background: None,
+            border: None,
+            dash: None,
+        }
+    }
 }
 
 impl Style {
@@ -30,6 +25,7 @@ impl Style {
             color: raw.into(),
             background: None,
             border: None,
+            dash: None,
         }
     }
 
@@ -43,6 +39,21 @@ impl Style {
         self
     }
 
+    pub fn with_fill(mut self, background: Color) -> Self {
+        self.background = Some(background);
+        self
+    }
+
+    /// Dashed strokes, alternating `on` drawn and `off` skipped pixels
+    pub fn with_dash(mut self, on: f64, off: f64) -> Self {
+        self.dash = Some((on, off));
+        self
+    }
+
+    pub fn get_dash(&self) -> Option<(f64, f64)> {
+        self.dash
+    }
+
     pub fn has_fill(&self) -> bool {
         self.get_frame().is_none() || (self.get_frame().is_some() && self.background.is_some())
     }
@@ -1,46 +1,179 @@
-use crate::{Color, Dimension, ImageRenderer, Point, Renderable, ShapeRenderer};
+use crate::{Color, Dimension, ImageRenderer, Meta, PathSegment, Point, Renderable, ShapeRenderer};
 use rsound_output::*;
+use std::collections::HashMap;
 
+#[derive(Clone)]
 pub struct Renderer {
     size: Dimension,
     buffer: Vec<u8>,
+    sweep: Option<Sweep>,
+    /// Distinct paints shared through generated classes, in stylesheet mode
+    styles: Option<Vec<String>>,
+    /// Index of each rule in `styles`
+    classes: HashMap<String, usize>,
+    /// Sized by the container rather than by width and height
+    responsive: bool,
+    /// Decimals kept of numbers, with whitespace left out, when minified
+    precision: Option<i32>,
+}
+
+/// Presentation properties of an element, as attributes or as a CSS rule
+type Paint = Vec<(&'static str, String)>;
+
+/// Playhead crossing a plot area from left to right, over and over
+#[derive(Debug, Copy, Clone)]
+pub struct Sweep {
+    pub(crate) area: (Point, Dimension),
+    pub(crate) seconds: f64,
+    pub(crate) color: Color,
+    pub(crate) tint: Option<Color>,
+    pub(crate) highlight: Option<Color>,
+}
+
+impl Sweep {
+    /// Takes `seconds` to cross the area, as returned by `Plot::area`
+    pub fn new(area: (Point, Dimension), seconds: f64) -> Self {
+        Self {
+            area,
+            seconds,
+            color: Color::from(0xFFFFFF),
+            tint: None,
+            highlight: None,
+        }
+    }
+
+    pub fn with_color(mut self, color: u32) -> Self {
+        self.color = color.into();
+        self
+    }
+
+    /// Shades what has been played so far
+    pub fn with_tint(mut self, color: u32) -> Self {
+        self.tint = Some(Color::from(color).with_alpha(64));
+        self
+    }
+
+    /// Fills notes, the rects tagged with class `note`, with the color while
+    /// the playhead crosses them
+    pub fn with_highlight(mut self, color: u32) -> Self {
+        self.highlight = Some(color.into());
+        self
+    }
+
+    /// Never ending SMIL animation of `attribute`
+    fn animate(&self, attribute: &str, values: &str) -> String {
+        format!(
+            "<animate attributeName='{}' {} dur='{}s' repeatCount='indefinite' />",
+            attribute, values, self.seconds
+        )
+    }
+
+    /// Share of the sweep done when the playhead reaches `x`
+    fn progress(&self, x: f64) -> f64 {
+        let (area, size) = self.area;
+        ((x - area.x) / size.w).clamp(0.0, 1.0)
+    }
+
+    /// Fill switching to the highlight while the playhead is over the note
+    fn highlight(
+        &self,
+        pos: Point,
+        size: Dimension,
+        color: &Color,
+        meta: Option<&Meta>,
+    ) -> Option<String> {
+        let highlight = self.highlight?;
+        if !meta.is_some_and(|x| x.get_classes().iter().any(|x| x == "note")) {
+            return None;
+        }
+        let (from, to) = (self.progress(pos.x), self.progress(pos.x + size.w));
+        let mut keys = Vec::new();
+        if from > 0.0 {
+            keys.push((0.0, *color));
+        }
+        keys.push((from, highlight));
+        if to < 1.0 {
+            keys.push((to, *color));
+        }
+        let values = keys
+            .iter()
+            .map(|x| x.1.rgb())
+            .collect::<Vec<String>>()
+            .join(";");
+        let times = keys
+            .iter()
+            .map(|x| format!("{}", x.0))
+            .collect::<Vec<String>>()
+            .join(";");
+        Some(self.animate(
+            "fill",
+            &format!(
+                "values='{}' keyTimes='{}' calcMode='discrete'",
+                values, times
+            ),
+        ))
+    }
+
+    /// Tint and playhead, drawn over everything else
+    fn overlay(&self) -> String {
+        let (area, size) = self.area;
+        let mut overlay = String::new();
+        if let Some(tint) = self.tint {
+            overlay += &format!(
+                "<rect x='{}' y='{}' width='0' height='{}' fill='{}'>{}</rect>\n",
+                area.x,
+                area.y,
+                size.h,
+                tint.rgb(),
+                self.animate("width", &format!("from='0' to='{}'", size.w))
+            );
+        }
+        overlay += &format!(
+            "<rect x='{}' y='{}' width='2' height='{}' fill='{}'>{}</rect>\n",
+            area.x - 1.0,
+            area.y,
+            size.h,
+            self.color.rgb(),
+            self.animate(
+                "x",
+                &format!("from='{}' to='{}'", area.x - 1.0, area.x + size.w - 1.0)
+            )
+        );
+        overlay
+    }
 }
 
 impl OutputRenderer for Renderer {
     fn get_header(&self) -> Option<Vec<u8>> {
-        Some(
-            format!(
-                "<svg version='1.1' width='{}' height='{}' xmlns='http://www.w3.org/2000/svg'>\n",
-                self.size.w as usize, self.size.h as usize
-            )
-            .into_bytes(),
-        )
+        let (w, h) = (self.size.w as usize, self.size.h as usize);
+        let size = match self.responsive {
+            true => String::new(),
+            false => format!(" width='{}' height='{}'", w, h),
+        };
+        let mut header = format!(
+            "<svg version='1.1'{} viewBox='0 0 {} {}' xmlns='http://www.w3.org/2000/svg'>",
+            size, w, h
+        );
+        header += self.separator();
+        if let Some(styles) = &self.styles {
+            header += &format!("<style>{}", self.separator());
+            for (idx, style) in styles.iter().enumerate() {
+                header += &format!(".s{}{{{}}}{}", idx, style, self.separator());
+            }
+            header += &format!("</style>{}", self.separator());
+        }
+        Some(header.into_bytes())
     }
 
     fn get_footer(&self) -> Option<Vec<u8>> {
-        Some("</svg>".as_bytes().to_vec())
+        let overlay = self.sweep.map(|x| x.overlay()).unwrap_or_default();
+        Some(format!("{}</svg>", overlay).into_bytes())
     }
 }
 
 impl ShapeRenderer for Renderer {
     fn draw(&mut self, shape: Renderable) {
-        match shape {
-            Renderable::Rect(pos, size, style) => {
-                if style.has_fill() {
-                    self.rect(pos, size, style.get_color());
-                }
-                if let Some((color, thickness)) = style.get_frame() {
-                    self.frame(pos, size, color, thickness);
-                }
-            }
-            Renderable::Line(p1, p2, style) => {
-                if let Some((color, thickness)) = style.get_frame() {
-                    self.line(p1, p2, color, thickness);
-                } else if style.has_fill() {
-                    self.line(p1, p2, style.get_color(), 1.0);
-                }
-            }
-        }
+        self.shape(shape, None);
     }
 }
 
@@ -60,44 +193,457 @@ impl Renderer {
                 h: size.h,
             },
             buffer: Vec::new(),
+            sweep: None,
+            styles: None,
+            classes: HashMap::new(),
+            responsive: false,
+            precision: None,
         }
     }
 
-    fn rect(&mut self, pos: Point, size: Dimension, color: &Color) {
-        let rect = format!(
-            "<rect x='{}' y='{}' width='{}' height='{}' fill='{}' />\n",
-            pos.x,
-            pos.y,
-            size.w,
-            size.h,
-            color.rgb()
+    /// Animated playhead on top of the drawing
+    pub fn with_sweep(mut self, sweep: Sweep) -> Self {
+        self.sweep = Some(sweep);
+        self
+    }
+
+    /// Paints shared through classes of a `<style>` block instead of repeated
+    /// on every element
+    pub fn with_stylesheet(mut self) -> Self {
+        self.styles = Some(Vec::new());
+        self.classes.clear();
+        self
+    }
+
+    /// Leaves out width and height so the drawing scales to its container,
+    /// keeping the proportions of the `viewBox`
+    pub fn with_responsive(mut self) -> Self {
+        self.responsive = true;
+        self
+    }
+
+    /// Numbers rounded to `decimals`, no whitespace between elements
+    pub fn with_minify(mut self, decimals: u8) -> Self {
+        self.precision = Some(decimals as i32);
+        self
+    }
+
+    fn separator(&self) -> &'static str {
+        match self.precision {
+            Some(_) => "",
+            None => "\n",
+        }
+    }
+
+    fn number(&self, value: f64) -> String {
+        match self.precision {
+            Some(decimals) => {
+                let scale = 10f64.powi(decimals);
+                // Adding zero turns a rounded negative zero positive
+                format!("{}", (value * scale).round() / scale + 0.0)
+            }
+            None => format!("{}", value),
+        }
+    }
+
+    fn color(&self, color: &Color) -> String {
+        match self.precision {
+            Some(_) => color.rgb().replace(' ', ""),
+            None => color.rgb(),
+        }
+    }
+
+    /// Class of the paint in stylesheet mode, added on first use
+    fn class(&mut self, paint: &Paint) -> Option<usize> {
+        let rule = paint
+            .iter()
+            .map(|(property, value)| format!("{}:{}", property, value))
+            .collect::<Vec<String>>()
+            .join(";");
+        let styles = self.styles.as_mut()?;
+        Some(*self.classes.entry(rule).or_insert_with_key(|rule| {
+            styles.push(rule.clone());
+            styles.len() - 1
+        }))
+    }
+
+    /// Elements of a shape, its metadata on the element or on a group around
+    /// them when the shape takes several
+    fn shape(&mut self, shape: Renderable, meta: Option<&Meta>) {
+        match shape {
+            Renderable::Rect(pos, size, style) => {
+                if style.has_fill() {
+                    self.rect(pos, size, style.get_color(), style.get_frame(), meta);
+                } else if let Some((color, thickness)) = style.get_frame() {
+                    self.frame(pos, size, color, thickness, meta);
+                }
+            }
+            Renderable::Line(p1, p2, style) => {
+                if let Some((color, thickness)) = style.get_frame() {
+                    self.line(p1, p2, color, thickness, style.get_dash(), meta);
+                } else if style.has_fill() {
+                    self.line(p1, p2, style.get_color(), 1.0, style.get_dash(), meta);
+                }
+            }
+            Renderable::Text(pos, text, style) => {
+                self.text(pos, &text, style.get_color(), meta);
+            }
+            Renderable::Polygon(points, style) => match meta {
+                Some(meta) if style.has_fill() && style.get_frame().is_some() => {
+                    self.group(vec![Renderable::Polygon(points, style)], meta);
+                }
+                _ => {
+                    if style.has_fill() {
+                        self.polygon(&points, style.get_color(), meta);
+                    }
+                    if let Some((color, thickness)) = style.get_frame() {
+                        self.outline(&points, color, thickness, style.get_dash(), meta);
+                    }
+                }
+            },
+            Renderable::Path(segments, style) => {
+                if let Some((color, thickness)) = style.get_frame() {
+                    self.path(&segments, color, thickness, style.get_dash(), meta);
+                } else if style.has_fill() {
+                    self.path(&segments, style.get_color(), 1.0, style.get_dash(), meta);
+                }
+            }
+            Renderable::Tagged(shape, inner) => match meta {
+                Some(meta) => self.group(vec![Renderable::Tagged(shape, inner)], meta),
+                None => self.shape(*shape, Some(&inner)),
+            },
+            Renderable::Group(shapes, inner) => match meta {
+                Some(meta) => self.group(vec![Renderable::Group(shapes, inner)], meta),
+                None => self.group(shapes, &inner),
+            },
+        }
+    }
+
+    fn group(&mut self, shapes: Vec<Renderable>, meta: &Meta) {
+        let open = format!(
+            "<g{}>{}{}",
+            attributes(Some(meta), None),
+            title(Some(meta)),
+            self.separator()
         );
-        self.buffer.append(&mut rect.into_bytes());
+        self.buffer.append(&mut open.into_bytes());
+        for shape in shapes {
+            self.shape(shape, None);
+        }
+        let close = format!("</g>{}", self.separator());
+        self.buffer.append(&mut close.into_bytes());
     }
 
-    fn frame(&mut self, pos: Point, size: Dimension, color: &Color, thickness: f64) {
-        let rect = format!(
-            "<rect x='{}' y='{}' width='{}' height='{}' color='{}' stroke='{}' fill='transparent'/>\n",
-            pos.x,
-            pos.y,
-            size.w,
-            size.h,
-            color.rgb(),
-            thickness
+    /// Writes an element with its metadata, its paint inline unless there is
+    /// a stylesheet or it is animated
+    fn element(
+        &mut self,
+        name: &str,
+        geometry: String,
+        paint: Paint,
+        content: Option<String>,
+        animated: bool,
+        meta: Option<&Meta>,
+    ) {
+        let (class, paint) = match self.class(&paint).filter(|_| !animated) {
+            Some(idx) => (Some(format!("s{}", idx)), String::new()),
+            None => (
+                None,
+                paint
+                    .iter()
+                    .map(|(property, value)| format!(" {}='{}'", property, value))
+                    .collect(),
+            ),
+        };
+        let open = format!("<{}{} {}{}", name, attributes(meta, class), geometry, paint);
+        let content = title(meta) + &content.unwrap_or_default();
+        let element = match content.is_empty() {
+            true => format!("{} />{}", open, self.separator()),
+            false => format!("{}>{}</{}>{}", open, content, name, self.separator()),
+        };
+        self.buffer.append(&mut element.into_bytes());
+    }
+
+    fn rect(
+        &mut self,
+        pos: Point,
+        size: Dimension,
+        color: &Color,
+        frame: Option<(&Color, f64)>,
+        meta: Option<&Meta>,
+    ) {
+        let highlight = self
+            .sweep
+            .and_then(|sweep| sweep.highlight(pos, size, color, meta));
+        let geometry = format!(
+            "x='{}' y='{}' width='{}' height='{}'",
+            self.number(pos.x),
+            self.number(pos.y),
+            self.number(size.w),
+            self.number(size.h)
+        );
+        let mut paint = vec![("fill", self.color(color))];
+        if let Some((stroke, thickness)) = frame {
+            paint.push(("stroke", self.color(stroke)));
+            paint.push(("stroke-width", self.number(thickness)));
+        }
+        let animated = highlight.is_some();
+        self.element("rect", geometry, paint, highlight, animated, meta);
+    }
+
+    fn frame(
+        &mut self,
+        pos: Point,
+        size: Dimension,
+        color: &Color,
+        thickness: f64,
+        meta: Option<&Meta>,
+    ) {
+        let geometry = format!(
+            "x='{}' y='{}' width='{}' height='{}'",
+            self.number(pos.x),
+            self.number(pos.y),
+            self.number(size.w),
+            self.number(size.h)
+        );
+        let paint = vec![
+            ("stroke", self.color(color)),
+            ("stroke-width", self.number(thickness)),
+            ("fill", "transparent".to_string()),
+        ];
+        self.element("rect", geometry, paint, None, false, meta);
+    }
+
+    /// Stroke of lines and outlines, dashed or not
+    fn stroke(&self, color: &Color, thickness: f64, dash: Option<(f64, f64)>) -> Paint {
+        let mut paint = vec![
+            ("stroke", self.color(color)),
+            ("stroke-width", self.number(thickness)),
+        ];
+        if let Some((on, off)) = dash {
+            paint.push((
+                "stroke-dasharray",
+                format!("{} {}", self.number(on), self.number(off)),
+            ));
+        }
+        paint
+    }
+
+    fn line(
+        &mut self,
+        p1: Point,
+        p2: Point,
+        color: &Color,
+        thickness: f64,
+        dash: Option<(f64, f64)>,
+        meta: Option<&Meta>,
+    ) {
+        let geometry = format!(
+            "x1='{}' y1='{}' x2='{}' y2='{}'",
+            self.number(p1.x),
+            self.number(p1.y),
+            self.number(p2.x),
+            self.number(p2.y)
         );
-        self.buffer.append(&mut rect.into_bytes());
-    }
-
-    fn line(&mut self, p1: Point, p2: Point, color: &Color, thickness: f64) {
-        let rect = format!(
-            "<line x1='{}' y1='{}' x2='{}' y2='{}' stroke='{}' stroke-width='{}' />\n",
-            p1.x,
-            p1.y,
-            p2.x,
-            p2.y,
-            color.rgb(),
-            thickness,
+        let paint = self.stroke(color, thickness, dash);
+        self.element("line", geometry, paint, None, false, meta);
+    }
+
+    fn polygon(&mut self, points: &[Point], color: &Color, meta: Option<&Meta>) {
+        let geometry = format!("points='{}'", self.coordinates(points));
+        let paint = vec![("fill", self.color(color))];
+        self.element("polygon", geometry, paint, None, false, meta);
+    }
+
+    fn outline(
+        &mut self,
+        points: &[Point],
+        color: &Color,
+        thickness: f64,
+        dash: Option<(f64, f64)>,
+        meta: Option<&Meta>,
+    ) {
+        let geometry = format!("points='{}'", self.coordinates(points));
+        let mut paint = self.stroke(color, thickness, dash);
+        paint.push(("fill", "transparent".to_string()));
+        self.element("polygon", geometry, paint, None, false, meta);
+    }
+
+    fn path(
+        &mut self,
+        segments: &[PathSegment],
+        color: &Color,
+        thickness: f64,
+        dash: Option<(f64, f64)>,
+        meta: Option<&Meta>,
+    ) {
+        let geometry = format!("d='{}'", self.commands(segments));
+        let mut paint = self.stroke(color, thickness, dash);
+        paint.push(("fill", "none".to_string()));
+        self.element("path", geometry, paint, None, false, meta);
+    }
+
+    fn text(&mut self, pos: Point, text: &str, color: &Color, meta: Option<&Meta>) {
+        let geometry = format!("x='{}' y='{}'", self.number(pos.x), self.number(pos.y));
+        let paint = vec![
+            ("fill", self.color(color)),
+            ("font-family", "monospace".to_string()),
+            ("font-size", "10px".to_string()),
+            ("dominant-baseline", "hanging".to_string()),
+        ];
+        self.element("text", geometry, paint, Some(escape(text)), false, meta);
+    }
+
+    fn coordinates(&self, points: &[Point]) -> String {
+        points
+            .iter()
+            .map(|p| format!("{},{}", self.number(p.x), self.number(p.y)))
+            .collect::<Vec<String>>()
+            .join(" ")
+    }
+
+    fn commands(&self, segments: &[PathSegment]) -> String {
+        let n = |p: &Point| format!("{},{}", self.number(p.x), self.number(p.y));
+        segments
+            .iter()
+            .map(|segment| match segment {
+                PathSegment::MoveTo(p) => format!("M{}", n(p)),
+                PathSegment::LineTo(p) => format!("L{}", n(p)),
+                PathSegment::CubicTo(c1, c2, p) => format!("C{} {} {}", n(c1), n(c2), n(p)),
+            })
+            .collect::<Vec<String>>()
+            .join(" ")
+    }
+}
+
+/// Id, classes and data of an element, each with a leading space, the class
+/// of its paint joining those of the metadata
+fn attributes(meta: Option<&Meta>, paint: Option<String>) -> String {
+    let mut attributes = String::new();
+    if let Some(id) = meta.and_then(|x| x.get_id()) {
+        attributes += &format!(" id='{}'", escape(id));
+    }
+    let mut classes = meta.map(|x| x.get_classes().to_vec()).unwrap_or_default();
+    classes.extend(paint);
+    if !classes.is_empty() {
+        attributes += &format!(" class='{}'", escape(&classes.join(" ")));
+    }
+    for (key, value) in meta.map(|x| x.get_data()).unwrap_or_default() {
+        attributes += &format!(" data-{}='{}'", escape(key), escape(value));
+    }
+    attributes
+}
+
+fn title(meta: Option<&Meta>) -> String {
+    meta.and_then(|x| x.get_title())
+        .map(|title| format!("<title>{}</title>", escape(title)))
+        .unwrap_or_default()
+}
+
+fn escape(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('\'', "&apos;")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{Block, Graph, Roll, Style};
+
+    fn render(renderer: &mut Renderer) -> String {
+        let blocks = [Block(4.0, 1.0), Block(2.0, 3.0), Block(4.0, 1.0)];
+        Roll::new(&blocks).draw(renderer);
+        let mut svg = renderer.get_header().unwrap();
+        svg.extend_from_slice(renderer.get_buffer());
+        svg.append(&mut renderer.get_footer().unwrap());
+        String::from_utf8(svg).unwrap()
+    }
+
+    #[test]
+    fn stylesheet_shares_paint() {
+        let size = Dimension::new(280.0, 140.0);
+        let inline = render(&mut Renderer::new(&size));
+        let styled = render(&mut Renderer::new(&size).with_stylesheet());
+
+        assert!(styled.starts_with(
+            "<svg version='1.1' width='280' height='140' viewBox='0 0 280 140' \
+             xmlns='http://www.w3.org/2000/svg'>\n\
+             <style>\n.s0{fill:rgb(6, 9, 16)}\n.s1{fill:rgb(48, 48, 48)}\n"
+        ));
+        assert!(styled.contains("<rect class='s1' x='40' y='40' width='200' height='1' />"));
+        assert!(styled.contains(
+            "<rect class='note roll s2' data-duration='2' data-intensity='3' x='120' y='40'"
+        ));
+        assert!(
+            inline.starts_with("<svg version='1.1' width='280' height='140' viewBox="),
+            "same size either way"
+        );
+        assert_eq!(styled.matches("fill='").count(), 0, "no inline paint");
+        assert!(styled.len() < inline.len());
+    }
+
+    #[test]
+    fn minified_numbers_and_whitespace() {
+        let mut renderer = Renderer::new(&Dimension::new(10.0, 10.0))
+            .with_stylesheet()
+            .with_minify(1)
+            .with_responsive();
+        renderer.draw(Renderable::Rect(
+            Point {
+                x: 1.0 / 3.0,
+                y: -0.01,
+            },
+            Dimension { w: 2.25, h: 4.0 },
+            Style::color(0xFF0000),
+        ));
+        renderer.draw(Renderable::Text(
+            Point { x: 0.0, y: 5.0 },
+            "a".to_string(),
+            Style::color(0xFF0000),
+        ));
+
+        assert_eq!(
+            String::from_utf8(renderer.get_header().unwrap()).unwrap(),
+            "<svg version='1.1' viewBox='0 0 10 10' xmlns='http://www.w3.org/2000/svg'>\
+             <style>.s0{fill:rgb(255,0,0)}\
+             .s1{fill:rgb(255,0,0);font-family:monospace;font-size:10px;dominant-baseline:hanging}\
+             </style>"
+        );
+        assert_eq!(
+            renderer.get_buffer(),
+            b"<rect class='s0' x='0.3' y='0' width='2.3' height='4' />\
+              <text class='s1' x='0' y='5'>a</text>"
+        );
+    }
+
+    #[test]
+    fn metadata_on_elements() {
+        let mut renderer = Renderer::new(&Dimension::new(10.0, 10.0));
+        let points = vec![Point { x: 0.0, y: 0.0 }, Point { x: 4.0, y: 4.0 }];
+        let style = Style::color(0x00FF00)
+            .with_border(1.0)
+            .with_background(0xFF0000);
+        renderer.draw(
+            Renderable::Polygon(points.clone(), Style::color(0xFF0000))
+                .with_meta(Meta::class("area").with_id("a")),
+        );
+        renderer.draw(Renderable::Polygon(points, style).with_meta(Meta::class("box")));
+        renderer.draw(
+            Renderable::Group(vec![], Meta::class("data"))
+                .with_meta(Meta::default().with_title("<none>")),
+        );
+        let svg = String::from_utf8(renderer.get_buffer().to_vec()).unwrap();
+
+        assert!(svg.starts_with(
+            "<polygon id='a' class='area' points='0,0 4,4' fill='rgb(255, 0, 0)' />\n"
+        ));
+        assert!(
+            svg.contains("<g class='box'>\n<polygon points"),
+            "fill and outline"
         );
-        self.buffer.append(&mut rect.into_bytes());
+        assert_eq!(svg.matches("<polygon").count(), 3);
+        assert!(svg.ends_with("<g><title>&lt;none&gt;</title>\n<g class='data'>\n</g>\n</g>\n"));
     }
 }
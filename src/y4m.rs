@@ -0,0 +1,234 @@
+//! YUV4MPEG2 video, for piping frames into an external encoder.
+
+use crate::animation::Frames;
+use crate::Dimension;
+use rsound_output::*;
+use std::io::{self, Write};
+
+/// Uncompressed video of full resolution 4:4:4 frames, studio range BT.601
+pub struct Video {
+    size: Dimension,
+    fps: f64,
+    frames: usize,
+    buffer: Vec<u8>,
+}
+
+impl Video {
+    pub fn new(size: &Dimension, fps: f64) -> Self {
+        Self {
+            size: *size,
+            fps,
+            frames: 0,
+            buffer: Vec::new(),
+        }
+    }
+
+    pub fn frames(&self) -> usize {
+        self.frames
+    }
+}
+
+/// Video written out as frames are pushed, the header going first, so long
+/// renders never sit in memory. Writing stops at the first error, which
+/// `finish` hands back.
+pub struct Stream<W: Write> {
+    writer: W,
+    size: Dimension,
+    fps: f64,
+    frames: usize,
+    error: Option<io::Error>,
+}
+
+impl<W: Write> Stream<W> {
+    pub fn new(writer: W, size: &Dimension, fps: f64) -> Self {
+        Self {
+            writer,
+            size: *size,
+            fps,
+            frames: 0,
+            error: None,
+        }
+    }
+
+    pub fn frames(&self) -> usize {
+        self.frames
+    }
+
+    /// Flushes the writer and returns it, or the first error met. A stream
+    /// without frames still gets its header.
+    pub fn finish(mut self) -> io::Result<W> {
+        if self.frames == 0 && self.error.is_none() {
+            let header = header(&self.size, self.fps);
+            self.error = self.writer.write_all(&header).err();
+        }
+        match self.error {
+            Some(error) => Err(error),
+            None => {
+                self.writer.flush()?;
+                Ok(self.writer)
+            }
+        }
+    }
+}
+
+/// Frame rate as a fraction, to the thousandth
+fn rate(fps: f64) -> (u64, u64) {
+    let (mut num, mut den) = ((fps * 1000.0).round() as u64, 1000);
+    let (mut a, mut b) = (num, den);
+    while b != 0 {
+        (a, b) = (b, a % b);
+    }
+    if a > 1 {
+        num /= a;
+        den /= a;
+    }
+    (num, den)
+}
+
+fn header(size: &Dimension, fps: f64) -> Vec<u8> {
+    let (num, den) = rate(fps);
+    format!(
+        "YUV4MPEG2 W{} H{} F{}:{} Ip A1:1 C444\n",
+        size.width() as usize,
+        size.height() as usize,
+        num,
+        den
+    )
+    .into_bytes()
+}
+
+/// Frame marker and planes of a frame of packed RGB
+fn frame(size: &Dimension, rgb: &[u8]) -> Vec<u8> {
+    let pixels = (size.width() * size.height()) as usize;
+    assert_eq!(
+        rgb.len(),
+        pixels * 3,
+        "frame has to be the size of the video"
+    );
+    let mut frame = b"FRAME\n".to_vec();
+    let start = frame.len();
+    frame.resize(start + pixels * 3, 0);
+    for (idx, pixel) in rgb.chunks_exact(3).enumerate() {
+        let (y, u, v) = yuv(pixel[0], pixel[1], pixel[2]);
+        frame[start + idx] = y;
+        frame[start + pixels + idx] = u;
+        frame[start + pixels * 2 + idx] = v;
+    }
+    frame
+}
+
+/// Limited range luma and chroma of an RGB pixel
+fn yuv(r: u8, g: u8, b: u8) -> (u8, u8, u8) {
+    let (r, g, b) = (r as i32, g as i32, b as i32);
+    let y = ((66 * r + 129 * g + 25 * b + 128) >> 8) + 16;
+    let u = ((-38 * r - 74 * g + 112 * b + 128) >> 8) + 128;
+    let v = ((112 * r - 94 * g - 18 * b + 128) >> 8) + 128;
+    (y as u8, u as u8, v as u8)
+}
+
+impl Frames for Video {
+    /// Frame of the video's size, as packed RGB
+    fn push(&mut self, rgb: &[u8]) {
+        self.buffer.append(&mut frame(&self.size, rgb));
+        self.frames += 1;
+    }
+}
+
+impl<W: Write> Frames for Stream<W> {
+    /// Frame of the video's size, as packed RGB, written right away
+    fn push(&mut self, rgb: &[u8]) {
+        let mut bytes = frame(&self.size, rgb);
+        if self.error.is_some() {
+            return;
+        }
+        if self.frames == 0 {
+            let mut header = header(&self.size, self.fps);
+            header.append(&mut bytes);
+            bytes = header;
+        }
+        self.error = self.writer.write_all(&bytes).err();
+        self.frames += 1;
+    }
+}
+
+impl Buffer for Video {
+    fn get_buffer(&self) -> &[u8] {
+        &self.buffer
+    }
+}
+
+impl OutputRenderer for Video {
+    fn get_header(&self) -> Option<Vec<u8>> {
+        Some(header(&self.size, self.fps))
+    }
+
+    fn get_footer(&self) -> Option<Vec<u8>> {
+        None
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn header_and_planes() {
+        let mut video = Video::new(&Dimension::new(2.0, 1.0), 29.97);
+        video.push(&[255, 255, 255, 0, 0, 0]);
+        video.push(&[255, 0, 0, 0, 0, 255]);
+
+        assert_eq!(
+            video.get_header(),
+            Some(b"YUV4MPEG2 W2 H1 F2997:100 Ip A1:1 C444\n".to_vec())
+        );
+        assert_eq!(video.frames(), 2);
+        assert_eq!(
+            &video.get_buffer()[..12],
+            b"FRAME\n\xEB\x10\x80\x80\x80\x80"
+        );
+        assert_eq!(video.get_buffer().len(), 2 * (6 + 6));
+        assert_eq!(yuv(255, 0, 0), (82, 90, 240));
+    }
+
+    #[test]
+    fn streams_as_frames_arrive() {
+        let size = Dimension::new(2.0, 1.0);
+        let mut video = Video::new(&size, 29.97);
+        let mut stream = Stream::new(Vec::new(), &size, 29.97);
+        for rgb in [[255, 255, 255, 0, 0, 0], [255, 0, 0, 0, 0, 255]] {
+            video.push(&rgb);
+            stream.push(&rgb);
+        }
+        let mut expected = video.get_header().unwrap();
+        expected.extend_from_slice(video.get_buffer());
+
+        assert_eq!(stream.frames(), 2);
+        assert_eq!(stream.finish().unwrap(), expected, "same bytes as buffered");
+        assert_eq!(
+            Stream::new(Vec::new(), &size, 25.0).finish().unwrap(),
+            b"YUV4MPEG2 W2 H1 F25:1 Ip A1:1 C444\n".to_vec(),
+            "header without frames"
+        );
+    }
+
+    #[test]
+    fn first_error_kept() {
+        struct Full;
+        impl Write for Full {
+            fn write(&mut self, _: &[u8]) -> io::Result<usize> {
+                Err(io::Error::new(io::ErrorKind::StorageFull, "full"))
+            }
+            fn flush(&mut self) -> io::Result<()> {
+                Ok(())
+            }
+        }
+        let mut stream = Stream::new(Full, &Dimension::new(1.0, 1.0), 25.0);
+        stream.push(&[0, 0, 0]);
+        stream.push(&[0, 0, 0]);
+
+        match stream.finish() {
+            Err(error) => assert_eq!(error.kind(), io::ErrorKind::StorageFull),
+            Ok(_) => panic!("expected write error"),
+        }
+    }
+}